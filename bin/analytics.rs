@@ -2,24 +2,38 @@
 
 use colored::*;
 
+use iarmap::ArchiveDiff;
+use iarmap::Attributes;
+use iarmap::Change;
+use iarmap::DiffOptions;
+use iarmap::InitTable;
+use iarmap::MapDiff;
+use iarmap::Measure;
 use iarmap::Module;
-use iarmap::ObjModuleTable;
+use iarmap::ModuleDelta;
+use iarmap::ModuleSummary;
+use iarmap::ObjectKey;
+use iarmap::RuntimeAttributes;
+use iarmap::StackUsage;
 
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 
+use std::cmp::Ordering;
 use std::fmt;
 
-/// ColoredDiffModule wraps a Module for showing with colored formatting.
+/// ColoredDiffModule wraps a ModuleDelta for showing with colored formatting.
 /// The wrapper is used when showing the delta between two modules.
 struct ColoredDiffModule {
-    m: Module
+    m: ModuleDelta
 }
 
 impl fmt::Display for ColoredDiffModule {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
-        let painter = |size: Option<i32>| -> ColoredString {
+        let painter = |size: Option<i64>| -> ColoredString {
             match size {
                 None => Module::size_to_string(size).normal(),
                 Some(v) => {
@@ -43,105 +57,268 @@ impl fmt::Display for ColoredDiffModule {
             ro_code,
             ro_data,
             rw_data
-        )
+        )?;
+        if self.m.has_abs() {
+            let ro_data_abs: ColoredString = painter(self.m.ro_data_abs);
+            let rw_data_abs: ColoredString = painter(self.m.rw_data_abs);
+            write!(
+                f,
+                " \t ro_data_abs: {} \t rw_data_abs: {}",
+                ro_data_abs,
+                rw_data_abs
+            )?;
+        }
+        Ok(())
     }
 }
 
-/// Run analytics on the left and right module summary tables
-pub fn analyze(left: Vec<ObjModuleTable>, right: Vec<ObjModuleTable>) {
+/// Chooses how the changed objects are reported
+pub struct Report {
+    /// The size that changes are sorted and filtered by
+    pub measure: Measure,
+    /// Sort by the change as a percent of the left size, rather than in bytes
+    pub relative: bool,
+    /// Show one list of the objects that changed the most, rather than the
+    /// objects of each archive
+    pub sorted: bool,
+    /// Show at most this many objects in the sorted list
+    pub top: Option<usize>,
+    /// Hide changes of fewer bytes
+    pub min_bytes: u64,
+    /// Hide changes of a smaller percent
+    pub min_percent: f64,
+}
 
-    show_module_differences(&left, &right);
+impl Default for Report {
+    fn default() -> Report {
+        Report {
+            measure: Measure::Total,
+            relative: false,
+            sorted: false,
+            top: None,
+            min_bytes: 0,
+            min_percent: 0.0,
+        }
+    }
+}
 
-    // Turn the vectors of maps into a single, large map
-    let (left, right): (HashMap<String, Module>, HashMap<String, Module>) =
-        twice(left, right, |v| {
-            let mut map = HashMap::new();
-            for obj in v {
-                for (key, val) in obj.table.clone() {
-                    map.insert(key, val);
-                }
-            }
-            map
-        });
+impl Report {
+    /// Returns true if the change is at least the byte and percent thresholds.
+    /// An object that grew from nothing is always shown. The sorted list only
+    /// shows objects whose measured size changed, while the archive view keeps
+    /// objects that changed in any size.
+    fn shows(&self, change: &Change) -> bool {
+        let bytes = change.bytes(self.measure);
+        (bytes != 0 || !self.sorted)
+            && bytes.unsigned_abs() >= self.min_bytes
+            && change.percent(self.measure).is_none_or(|percent| percent.abs() >= self.min_percent)
+    }
 
-    compare_objects(&left, &right);
+    /// Returns the size of the change that it is sorted by
+    fn rank(&self, change: &Change) -> f64 {
+        if self.relative {
+            change.percent(self.measure).map_or(f64::INFINITY, f64::abs)
+        } else {
+            change.bytes(self.measure).unsigned_abs() as f64
+        }
+    }
 }
 
-/// Show the differences in module archive names
-fn show_module_differences(left: &Vec<ObjModuleTable>, right: &Vec<ObjModuleTable>) {
+/// Run analytics on the left and right module summaries, matching archives
+/// with the options
+pub fn analyze(left: &ModuleSummary, right: &ModuleSummary, options: &DiffOptions, report: &Report) {
+    let diff = options.diff(left, right);
+    show_module_differences(&diff);
+    if report.sorted {
+        show_top_changes(&diff, report);
+    } else {
+        show_moved_objects(&diff);
+        for archive in diff.archives.iter().filter(|archive| !archive.is_empty()) {
+            show_archive_differences(archive, report);
+        }
+    }
+    if diff.is_empty() {
+        println!("Objects beween left and right were the same");
+    }
+}
+
+/// Show the objects that changed the most, largest first. Changes are shown as
+/// left minus right, as in the rest of the report.
+fn show_top_changes(diff: &MapDiff, report: &Report) {
+    let mut changes: Vec<Change> = diff.changes().into_iter().filter(|change| report.shows(change)).collect();
+    changes.sort_by(|a, b| report.rank(b).partial_cmp(&report.rank(a)).unwrap_or(Ordering::Equal));
+    let shown = report.top.unwrap_or(changes.len()).min(changes.len());
 
-    let (left, right): (HashSet<_>, HashSet<_>) = twice(left, right, |v| {
-        v.iter().map(|obj| obj.name.clone()).collect()
-    });
+    println!("Top {} of {} changed objects...", shown, changes.len());
+    for change in &changes[..shown] {
+        let bytes = -change.bytes(report.measure);
+        let percent = change.percent(report.measure).map_or("new".into(), |percent| format!("{:+.1}%", -percent));
+        let text = format!("{:>+10} {:>9}", bytes, percent);
+        let lines = [("L", &change.left), ("R", &change.right)]
+            .iter()
+            .filter_map(|&(side, row)| row.as_ref().map(|row| format!("{} line {}", side, row.line)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "\t{} {} ({})",
+            if bytes < 0 { text.red() } else { text.green() },
+            change.key,
+            lines
+        );
+    }
+}
 
-    if left != right {
+/// Show the archives unique to each map file
+fn show_module_differences(diff: &MapDiff) {
+    let (removed, added) = (diff.removed_archives(), diff.added_archives());
+    if !removed.is_empty() || !added.is_empty() {
         println!("Modules unique to left...");
-        let mut diff = left.difference(&right).collect::<Vec<_>>();
-        diff.sort();
-        for unique in diff {
-            println!("\tL- {}", unique.magenta());
+        for unique in removed {
+            println!("\tL- {} (line {})", unique.name.magenta(), unique.line);
         }
 
         println!("Modules unique to right...");
-        let mut diff = right.difference(&left).collect::<Vec<_>>();
-        diff.sort();
-        for unique in diff {
-            println!("\tR- {}", unique.yellow());
+        for unique in added {
+            println!("\tR- {} (line {})", unique.name.yellow(), unique.line);
         }
     } else {
         println!("No module differences");
     }
 }
 
-/// Compare objects across two map files
-fn compare_objects(left: &HashMap<String, Module>, right: &HashMap<String, Module>) {
-
-    let (lkeys, rkeys): (HashSet<&String>, HashSet<&String>) =
-        twice(left, right, |m| m.keys().collect());
+/// Show the objects that moved between archives
+fn show_moved_objects(diff: &MapDiff) {
+    if diff.moved_objects.is_empty() {
+        return;
+    }
 
-    if lkeys != rkeys {
-        println!("Objects unique to left...");
-        let mut diff = lkeys.difference(&rkeys).collect::<Vec<_>>();
-        diff.sort();
-        for unique in diff {
-            println!("\tL- {}", unique.magenta());
-            println!("\t   {}", left.get(*unique).unwrap());
+    println!("Objects moved between archives...");
+    for moved in &diff.moved_objects {
+        println!(
+            "\tM- {} from {} (line {}) to {} (line {})",
+            moved.from.row.name.cyan(),
+            moved.from.key.archive.magenta(),
+            moved.from.row.line,
+            moved.to.key.archive.yellow(),
+            moved.to.row.line
+        );
+        if !moved.delta.is_zero() {
+            println!("\tD- {}", ColoredDiffModule{ m: -moved.delta });
         }
+    }
+}
 
-        println!("Objects unique to right...");
-        let mut diff = rkeys.difference(&lkeys).collect::<Vec<_>>();
-        diff.sort();
-        for unique in diff {
-            println!("\tR- {}", unique.yellow());
-            println!("\t   {}", right.get(*unique).unwrap());
-        }
-    } else {
-        println!("No unique objects between left and right");
+/// Show the change in an archive's size, then the objects unique to each map
+/// file and the objects whose sizes differ, hiding the objects below the
+/// report's thresholds. Deltas are shown as left minus right.
+fn show_archive_differences(archive: &ArchiveDiff, report: &Report) {
+    println!("Difference in archive {}...", archive.path.cyan());
+    if let Some(ref l) = archive.left {
+        println!("\tL- {} \t (line {})", l.module, l.line);
     }
+    if let Some(ref r) = archive.right {
+        println!("\tR- {} \t (line {})", r.module, r.line);
+    }
+    println!("\tD- {}", ColoredDiffModule{ m: -archive.delta });
 
-    let mut no_difference = true;
-    let mut intersect: Vec<_> = lkeys.intersection(&rkeys).collect();
+    for unique in archive.removed_objects.iter().filter(|o| report.shows(&Change::removed(o))) {
+        println!("\tObject unique to left: {} (line {})", unique.row.name.magenta(), unique.row.line);
+        println!("\t   {}", unique.row.module);
+    }
+    for unique in archive.added_objects.iter().filter(|o| report.shows(&Change::added(o))) {
+        println!("\tObject unique to right: {} (line {})", unique.row.name.yellow(), unique.row.line);
+        println!("\t   {}", unique.row.module);
+    }
+
+    for change in archive.changed_objects.iter().filter(|c| report.shows(&Change::changed(c))) {
+        println!("\tDifference in {}...", change.left.name.cyan());
+        println!("\t\tL- {} \t (line {})", change.left.module, change.left.line);
+        println!("\t\tR- {} \t (line {})", change.right.module, change.right.line);
+        println!("\t\tD- {}", ColoredDiffModule{ m: -change.delta });
+    }
+}
+
+/// Compare the startup initialization cost of two map files
+pub fn compare_init_tables(left: &InitTable, right: &InitTable) {
+    show_size_change("Zero-initialized RAM", left.zero_initialized(), right.zero_initialized());
+    show_size_change("Copy-initialized RAM", left.copied(), right.copied());
+    show_size_change("Initializers in flash", left.initializers(), right.initializers());
+}
+
+/// Compare the worst-case stack depth of each call graph root
+pub fn compare_stack_usage(left: &StackUsage, right: &StackUsage) {
+    let (lroots, rroots): (HashSet<&String>, HashSet<&String>) =
+        twice(left, right, |u| u.roots.iter().map(|root| &root.function).collect());
+
+    let mut diff = lroots.difference(&rroots).collect::<Vec<_>>();
+    diff.sort();
+    for unique in diff {
+        println!("Stack root unique to left: {}", unique.magenta());
+    }
+    let mut diff = rroots.difference(&lroots).collect::<Vec<_>>();
+    diff.sort();
+    for unique in diff {
+        println!("Stack root unique to right: {}", unique.yellow());
+    }
+
+    let mut intersect: Vec<_> = lroots.intersection(&rroots).collect();
     intersect.sort();
-    for obj in intersect {
-        let l = left.get(*obj);
-        let r = right.get(*obj);
+    for function in intersect {
+        let l = left.root(function).and_then(|root| root.max_depth);
+        let r = right.root(function).and_then(|root| root.max_depth);
+        match (l, r) {
+            (Some(l), Some(r)) => show_size_change(&format!("Stack depth of {}", function), l, r),
+            (l, r) if l != r => println!("Stack depth of {} is unknown on one side", function.cyan()),
+            _ => (),
+        }
+    }
+}
 
-        if let (Some(l), Some(r)) = (l, r) {
-            if l == r {
-                continue;
-            }
+/// Compare the runtime model attributes of the image, and of the objects in
+/// both map files, matching objects by their archive's path in each summary.
+/// Objects with the same key are matched in order.
+pub fn compare_runtime_attributes(
+    left: &RuntimeAttributes,
+    lsummary: &ModuleSummary,
+    right: &RuntimeAttributes,
+    rsummary: &ModuleSummary,
+) {
+    show_attribute_differences("the image", &left.image, &right.image);
+    let mut right_objects: HashMap<ObjectKey, VecDeque<&Attributes>> = HashMap::new();
+    for (key, r) in right.keyed(rsummary) {
+        right_objects.entry(key).or_default().push_back(r);
+    }
+    for (key, l) in left.keyed(lsummary) {
+        if let Some(r) = right_objects.get_mut(&key).and_then(VecDeque::pop_front) {
+            show_attribute_differences(&key.to_string(), l, r);
+        }
+    }
+}
 
-            no_difference = false;
-            println!("Difference in {}...", obj.cyan());
-            println!("\tL- {}", l);
-            println!("\tR- {}", r);
-            println!("\tD- {}", ColoredDiffModule{ m: *l - *r });
+/// Show the attributes that differ between left and right
+fn show_attribute_differences(owner: &str, left: &Attributes, right: &Attributes) {
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    for key in keys {
+        let (l, r) = (left.get(key), right.get(key));
+        if l != r {
+            println!("Runtime attribute {} of {} differs...", key.cyan(), owner);
+            println!("\tL- {}", l.map_or("------", |v| v.as_str()));
+            println!("\tR- {}", r.map_or("------", |v| v.as_str()));
         }
     }
+}
 
-    if no_difference {
-        println!("Objects beween left and right were the same");
+/// Show a left and right size, and their difference, when they differ
+fn show_size_change(title: &str, left: u64, right: u64) {
+    if left == right {
+        return;
     }
+    let delta = left as i64 - right as i64;
+    let delta = if delta < 0 { delta.to_string().red() } else { delta.to_string().green() };
+    println!("{}...", title.cyan());
+    println!("\tL- {}", left);
+    println!("\tR- {}", right);
+    println!("\tD- {}", delta);
 }
 
 //
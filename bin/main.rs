@@ -1,44 +1,143 @@
 //! # iarmapcmp
 //!
-//! The command-line program compares the module summary sections of two IAR map
-//! files.
+//! The command-line program compares the module summaries of two IAR map files,
+//! along with their init tables, stack usage, and runtime model attributes.
 //!
 //! ## Usage
 //!
 //! ```text
-//! iarmapcmp.exe [left-map-file] [right-map-file]
+//! iarmapcmp.exe [options] [left-map-file] [right-map-file]
 //! ```
+//!
+//! Archives are matched by path. When the map files were built from different
+//! checkouts, these options normalize the paths before matching:
+//!
+//! ```text
+//! --strip-prefix <prefix>   Strip the build root; may be repeated
+//! --components <n>          Match on the last n components of each path, or
+//!                           the whole path for 0
+//! --ignore-case             Match paths regardless of case
+//! --unify-separators        Treat \ and / as the same separator
+//! ```
+//!
+//! An object removed from one archive and added to another is shown as moved,
+//! if its size changed by at most 25%:
+//!
+//! ```text
+//! --move-tolerance <n>      Show objects whose size changed by at most n% as moved
+//! --no-moves                Show moved objects as removed and added
+//! ```
+//!
+//! By default, the changed objects are shown archive by archive. On large
+//! images, these options show only the changes that matter:
+//!
+//! ```text
+//! --sort <size>             List the objects that changed the most, where size
+//!                           is ro-code, ro-data, rw-data, ro-data-abs,
+//!                           rw-data-abs, or total
+//! --relative                List the objects that changed the most by percent
+//!                           change, rather than bytes
+//! --top <n>                 List at most n objects
+//! --min-bytes <n>           Hide changes of fewer than n bytes
+//! --min-percent <p>         Hide changes of less than p percent
+//! ```
+//!
+//! Every change is shown as left minus right, so an object that grew has a
+//! negative change.
 
 extern crate colored;
 extern crate iarmap;
 
 mod analytics;
-use analytics::analyze;
+use analytics::{analyze, compare_init_tables, compare_runtime_attributes, compare_stack_usage, Report};
 
 use std::env;
-use std::fs::File;
-use iarmap::parse_map_file;
+use std::fs;
+use iarmap::{DiffOptions, Measure, ParseOptions};
+
+/// The command-line arguments
+struct Args {
+    left: String,
+    right: String,
+    options: DiffOptions,
+    report: Report,
+}
+
+/// Returns the measure with the name used by `--sort`
+fn measure(name: &str) -> Option<Measure> {
+    match name {
+        "ro-code" => Some(Measure::RoCode),
+        "ro-data" => Some(Measure::RoData),
+        "rw-data" => Some(Measure::RwData),
+        "ro-data-abs" => Some(Measure::RoDataAbs),
+        "rw-data-abs" => Some(Measure::RwDataAbs),
+        "total" => Some(Measure::Total),
+        _ => None,
+    }
+}
 
 /// Handle command-line arguments
-fn handle_args(args: Vec<String>) -> Result<(String, String), &'static str> {
-    if args.len() != 3 {
-        Result::Err("Please provide the paths for two IAR map files")
+fn handle_args(args: Vec<String>) -> Result<Args, String> {
+    let mut options = DiffOptions::new();
+    let mut report = Report::default();
+    let mut files: Vec<String> = Vec::new();
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sort" => {
+                report.measure = args.next().and_then(|name| measure(&name)).ok_or("--sort needs ro-code, ro-data, rw-data, ro-data-abs, rw-data-abs, or total")?;
+                report.sorted = true;
+            }
+            "--relative" => {
+                report.relative = true;
+                report.sorted = true;
+            }
+            "--top" => {
+                report.top = Some(args.next().and_then(|n| n.parse().ok()).ok_or("--top needs a number")?);
+                report.sorted = true;
+            }
+            "--min-bytes" => report.min_bytes = args.next().and_then(|n| n.parse().ok()).ok_or("--min-bytes needs a number")?,
+            "--min-percent" => report.min_percent = args.next().and_then(|p| p.parse().ok()).ok_or("--min-percent needs a percent")?,
+            "--strip-prefix" => {
+                let prefix = args.next().ok_or("--strip-prefix needs a prefix")?;
+                options = options.strip_prefix(&prefix);
+            }
+            "--components" => {
+                let components = args.next().and_then(|n| n.parse().ok()).ok_or("--components needs a number")?;
+                options = options.trailing_components(components);
+            }
+            "--move-tolerance" => {
+                let percent = args.next().and_then(|n| n.parse().ok()).ok_or("--move-tolerance needs a percent")?;
+                options = options.move_tolerance(percent);
+            }
+            "--no-moves" => options = options.detect_moves(false),
+            "--ignore-case" => options = options.ignore_case(true),
+            "--unify-separators" => options = options.unify_separators(true),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => files.push(arg),
+        }
+    }
+
+    if files.len() != 2 {
+        Err("Please provide the paths for two IAR map files".into())
     } else {
-        Ok((args[1].clone(), args[2].clone()))
+        let right = files.pop().unwrap();
+        let left = files.pop().unwrap();
+        Ok(Args { left, right, options, report })
     }
 }
 
 fn main() {
 
     let args = handle_args(env::args().collect());
-    if let &Err(ref msg) = &args {
+    if let Err(msg) = &args {
         println!("Error: {}", msg);
         std::process::exit(1);
     }
 
-    let (left, right) = args.unwrap();
+    let Args { left, right, options: diff_options, report } = args.unwrap();
 
-    let (left, right) = match (File::open(left), File::open(right)) {
+    let (left, right) = match (fs::read(left), fs::read(right)) {
         (Ok(left), Ok(right)) => (left, right),
         (Err(msg), _) | (_, Err(msg)) => {
             println!("Error: {}", msg);
@@ -46,18 +145,80 @@ fn main() {
         }
     };
 
-    let left = parse_map_file(left).expect("Error on left file");
-    let right = parse_map_file(right).expect("Error on right file");
+    // Compare what could be parsed, and warn about the rows that could not
+    let options = ParseOptions::new().lenient(true);
+    let (mut left, mut right) = match (options.parse(&left[..]), options.parse(&right[..])) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Err(err), _) => {
+            println!("Error on left file: {}", err);
+            std::process::exit(1);
+        }
+        (_, Err(err)) => {
+            println!("Error on right file: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    for warning in &left.warnings {
+        println!("Warning on left file: {}", warning);
+    }
+    for warning in &right.warnings {
+        println!("Warning on right file: {}", warning);
+    }
+
+    // The banner is informational, so a map file without one is still compared
+    if let (Some(lheader), Some(rheader)) = (&left.header, &right.header) {
+        println!("Left:  {}", lheader);
+        println!("Right: {}", rheader);
+        if !lheader.same_linker(rheader) {
+            println!("Warning: the map files were produced by different linkers");
+        }
+    }
+
+    let (lsummary, rsummary) = match (left.module_summary.take(), right.module_summary.take()) {
+        (Some(left), Some(right)) => (left, right),
+        (None, _) => {
+            println!("Error on left file: no module summary");
+            std::process::exit(1);
+        }
+        (_, None) => {
+            println!("Error on right file: no module summary");
+            std::process::exit(1);
+        }
+    };
+
+    for mismatch in lsummary.validate() {
+        println!("Warning on left file: {}", mismatch);
+    }
+    for mismatch in rsummary.validate() {
+        println!("Warning on right file: {}", mismatch);
+    }
+    for duplicate in lsummary.duplicates() {
+        println!("Warning on left file: {}", duplicate);
+    }
+    for duplicate in rsummary.duplicates() {
+        println!("Warning on right file: {}", duplicate);
+    }
 
-    if left.len() < 1 {
+    if lsummary.tables.is_empty() {
         println!("Error on left file: no data");
         std::process::exit(1);
-    } else if right.len() < 1 {
+    } else if rsummary.tables.is_empty() {
         println!("Error on right file: no data");
         std::process::exit(1);
     }
 
+    analyze(&lsummary, &rsummary, &diff_options, &report);
 
-
-    analyze(left, right);
+    // Not every map file has an init table, stack usage, or runtime model
+    // attributes, so only compare them when both map files do
+    if let (Some(l), Some(r)) = (&left.init_table, &right.init_table) {
+        compare_init_tables(l, r);
+    }
+    if let (Some(l), Some(r)) = (&left.stack_usage, &right.stack_usage) {
+        compare_stack_usage(l, r);
+    }
+    if let (Some(l), Some(r)) = (&left.runtime_attributes, &right.runtime_attributes) {
+        compare_runtime_attributes(l, &lsummary, r, &rsummary);
+    }
 }
@@ -0,0 +1,768 @@
+//! The diff library module, which compares the module summaries of two map
+//! files
+
+use summary::{Module, ModuleDelta, ModuleRow, ModuleSummary, ObjectKey};
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// An archive of one map file: its name, the line of the map file that names
+/// it, and the sum of its objects' sizes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub line: usize,
+    pub module: Module,
+}
+
+/// An object of one map file, and its row of the module summary
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ObjectEntry {
+    pub key: ObjectKey,
+    pub row: ModuleRow,
+}
+
+/// An object in both map files whose sizes differ. The `delta` is the change
+/// from the left row to the right row.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ObjectChange {
+    pub key: ObjectKey,
+    pub left: ModuleRow,
+    pub right: ModuleRow,
+    pub delta: ModuleDelta,
+}
+
+/// An object only in one archive of the left map file, and only in another
+/// archive of the right map file, as when `crc.o` moves from `utils.a` to
+/// `core.a`. The `delta` is the change from the left row to the right row.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ObjectMove {
+    pub from: ObjectEntry,
+    pub to: ObjectEntry,
+    pub delta: ModuleDelta,
+}
+
+/// The differences within one archive, matched across map files by its `path`,
+/// as normalized by the `DiffOptions`.
+///
+/// `left` and `right` are the archive in each map file, and `None` if the
+/// archive is only in the other one. The `delta` is the change in the archive's
+/// size from left to right. `removed_objects` are only in the left archive, and
+/// `added_objects` are only in the right archive. Objects that moved to or from
+/// another archive are not listed here, but in `MapDiff::moved_objects`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ArchiveDiff {
+    pub path: String,
+    pub left: Option<ArchiveEntry>,
+    pub right: Option<ArchiveEntry>,
+    pub delta: ModuleDelta,
+    pub removed_objects: Vec<ObjectEntry>,
+    pub added_objects: Vec<ObjectEntry>,
+    pub changed_objects: Vec<ObjectChange>,
+}
+
+impl ArchiveDiff {
+    /// Returns true if the archive has the same objects, with the same sizes,
+    /// in both map files
+    pub fn is_empty(&self) -> bool {
+        self.left.is_some()
+            && self.right.is_some()
+            && self.delta.is_zero()
+            && self.removed_objects.is_empty()
+            && self.added_objects.is_empty()
+            && self.changed_objects.is_empty()
+    }
+}
+
+/// The differences between the module summaries of a left and a right map
+/// file, as computed by `diff`.
+///
+/// `archives` has an `ArchiveDiff` for every archive in either map file: first
+/// the archives of the left map file, in its order, then the archives only in
+/// the right map file. Archives are matched by path, ignoring their `[N]`
+/// index, and archives with the same path in a map file are treated as one.
+/// Objects are matched by name within their archive. An object listed more
+/// than once in an archive is matched row by row, so that the second row on the
+/// left is compared with the second row on the right.
+///
+/// An object removed from one archive and added to another, with the same
+/// name and a similar size, is listed once in `moved_objects` rather than in
+/// both archives.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MapDiff {
+    pub archives: Vec<ArchiveDiff>,
+    pub moved_objects: Vec<ObjectMove>,
+}
+
+impl MapDiff {
+    /// Returns true if the module summaries have the same archives and objects,
+    /// with the same sizes
+    pub fn is_empty(&self) -> bool {
+        self.archives.iter().all(ArchiveDiff::is_empty) && self.moved_objects.is_empty()
+    }
+
+    /// Returns the archives only in the left map file
+    pub fn removed_archives(&self) -> Vec<&ArchiveEntry> {
+        self.archives.iter().filter(|a| a.right.is_none()).filter_map(|a| a.left.as_ref()).collect()
+    }
+
+    /// Returns the archives only in the right map file
+    pub fn added_archives(&self) -> Vec<&ArchiveEntry> {
+        self.archives.iter().filter(|a| a.left.is_none()).filter_map(|a| a.right.as_ref()).collect()
+    }
+
+    /// Returns the objects only in the left map file
+    pub fn removed_objects(&self) -> Vec<&ObjectEntry> {
+        self.archives.iter().flat_map(|a| &a.removed_objects).collect()
+    }
+
+    /// Returns the objects only in the right map file
+    pub fn added_objects(&self) -> Vec<&ObjectEntry> {
+        self.archives.iter().flat_map(|a| &a.added_objects).collect()
+    }
+
+    /// Returns the objects whose sizes differ
+    pub fn changed_objects(&self) -> Vec<&ObjectChange> {
+        self.archives.iter().flat_map(|a| &a.changed_objects).collect()
+    }
+
+    /// Returns every removed, added, moved, and changed object as a `Change`,
+    /// in the order of the archives
+    pub fn changes(&self) -> Vec<Change> {
+        let mut changes: Vec<Change> = Vec::new();
+        for archive in &self.archives {
+            changes.extend(archive.removed_objects.iter().map(Change::removed));
+            changes.extend(archive.added_objects.iter().map(Change::added));
+            changes.extend(archive.changed_objects.iter().map(Change::changed));
+        }
+        changes.extend(self.moved_objects.iter().map(Change::moved));
+        changes
+    }
+}
+
+/// The sizes that a `Change` may be measured by
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Measure {
+    RoCode,
+    RoData,
+    RwData,
+    RoDataAbs,
+    RwDataAbs,
+    /// The sum of `ro code`, `ro data`, and `rw data`
+    Total,
+}
+
+impl Measure {
+    /// Returns the module's size
+    pub fn size(self, module: &Module) -> u64 {
+        match self {
+            Measure::RoCode => module.ro_code.unwrap_or(0),
+            Measure::RoData => module.ro_data.unwrap_or(0),
+            Measure::RwData => module.rw_data.unwrap_or(0),
+            Measure::RoDataAbs => module.ro_data_abs.unwrap_or(0),
+            Measure::RwDataAbs => module.rw_data_abs.unwrap_or(0),
+            Measure::Total => module.total(),
+        }
+    }
+
+    /// Returns the delta's change in size
+    pub fn change(self, delta: &ModuleDelta) -> i64 {
+        match self {
+            Measure::RoCode => delta.ro_code.unwrap_or(0),
+            Measure::RoData => delta.ro_data.unwrap_or(0),
+            Measure::RwData => delta.rw_data.unwrap_or(0),
+            Measure::RoDataAbs => delta.ro_data_abs.unwrap_or(0),
+            Measure::RwDataAbs => delta.rw_data_abs.unwrap_or(0),
+            Measure::Total => delta.total(),
+        }
+    }
+}
+
+/// The change in size of one object, from its `left` row to its `right` row.
+/// A removed object has no `right` row, and an added object has no `left` row.
+/// The `key` is the object in the right map file, if it is there.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Change {
+    pub key: ObjectKey,
+    pub left: Option<ModuleRow>,
+    pub right: Option<ModuleRow>,
+    pub delta: ModuleDelta,
+}
+
+impl Change {
+    /// The change of an object only in the left map file
+    pub fn removed(object: &ObjectEntry) -> Change {
+        let delta = &Module::default() - &object.row.module;
+        Change { key: object.key.clone(), left: Some(object.row.clone()), right: None, delta }
+    }
+
+    /// The change of an object only in the right map file
+    pub fn added(object: &ObjectEntry) -> Change {
+        let delta = &object.row.module - &Module::default();
+        Change { key: object.key.clone(), left: None, right: Some(object.row.clone()), delta }
+    }
+
+    /// The change of an object in the same archive of both map files
+    pub fn changed(change: &ObjectChange) -> Change {
+        Change {
+            key: change.key.clone(),
+            left: Some(change.left.clone()),
+            right: Some(change.right.clone()),
+            delta: change.delta,
+        }
+    }
+
+    /// The change of an object that moved between archives
+    pub fn moved(moved: &ObjectMove) -> Change {
+        Change {
+            key: moved.to.key.clone(),
+            left: Some(moved.from.row.clone()),
+            right: Some(moved.to.row.clone()),
+            delta: moved.delta,
+        }
+    }
+
+    /// Returns the change in bytes
+    pub fn bytes(&self, measure: Measure) -> i64 {
+        measure.change(&self.delta)
+    }
+
+    /// Returns the change as a percent of the left size, or `None` if the
+    /// object grew from nothing
+    pub fn percent(&self, measure: Measure) -> Option<f64> {
+        let (bytes, left) = (self.bytes(measure), self.left.as_ref().map_or(0, |row| measure.size(&row.module)));
+        match (bytes, left) {
+            (0, _) => Some(0.0),
+            (_, 0) => None,
+            (bytes, left) => Some(bytes as f64 * 100.0 / left as f64),
+        }
+    }
+}
+
+/// Chooses how archives are matched across map files.
+///
+/// By default, archives are matched by their exact path, which ignores only
+/// their `[N]` index. Map files built from different checkouts name the same
+/// archive with different absolute paths, so the path may be normalized
+/// before matching:
+///
+/// ```
+/// use iarmap::DiffOptions;
+///
+/// let options = DiffOptions::new()
+///     .unify_separators(true)
+///     .ignore_case(true)
+///     .strip_prefix(r"C:\ci\agent-7")
+///     .trailing_components(2);
+/// assert_eq!(options.normalize(r"C:\CI\agent-7\Foo\Bar\Obj"), "bar/obj");
+/// ```
+///
+/// By default, an object is also detected as moved when it is removed from one
+/// archive and added to another, if the sizes differ by at most 25%.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    prefixes: Vec<String>,
+    components: Option<usize>,
+    ignore_case: bool,
+    unify_separators: bool,
+    detect_moves: bool,
+    move_tolerance: u64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            prefixes: Vec::new(),
+            components: None,
+            ignore_case: false,
+            unify_separators: false,
+            detect_moves: true,
+            move_tolerance: 25,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Options that match archives by their exact path
+    pub fn new() -> DiffOptions {
+        DiffOptions::default()
+    }
+
+    /// Strip the prefix from the paths that start with it. The prefix must end
+    /// at a separator or at the end of the path, so `C:\ci\agent` strips
+    /// `C:\ci\agent\Foo` but not `C:\ci\agent-7\Foo`. May be called more
+    /// than once, such as for the build root of each map file.
+    pub fn strip_prefix(mut self, prefix: &str) -> DiffOptions {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Match on the last `components` of each path, such as `Baz/Obj` for 2.
+    /// 0 keeps the whole path, as if the option was not given.
+    pub fn trailing_components(mut self, components: usize) -> DiffOptions {
+        self.components = Some(components).filter(|components| *components > 0);
+        self
+    }
+
+    /// Match paths regardless of case, as on Windows
+    pub fn ignore_case(mut self, ignore_case: bool) -> DiffOptions {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Treat `\` and `/` as the same separator
+    pub fn unify_separators(mut self, unify_separators: bool) -> DiffOptions {
+        self.unify_separators = unify_separators;
+        self
+    }
+
+    /// Report objects that moved between archives as moves, rather than as
+    /// removed and added objects
+    pub fn detect_moves(mut self, detect_moves: bool) -> DiffOptions {
+        self.detect_moves = detect_moves;
+        self
+    }
+
+    /// The most that a moved object's total size may change, as a percent of
+    /// the larger size
+    pub fn move_tolerance(mut self, percent: u64) -> DiffOptions {
+        self.move_tolerance = percent;
+        self
+    }
+
+    /// Returns true if the objects are similar enough in size to be a move
+    /// A tolerance so large that the limit overflows allows any change.
+    fn similar(&self, from: &Module, to: &Module) -> bool {
+        let (from, to) = (every_size(from), every_size(to));
+        from.max(to)
+            .checked_mul(u128::from(self.move_tolerance))
+            .is_none_or(|limit| from.max(to) - from.min(to) <= limit / 100)
+    }
+
+    /// Takes the objects that moved between archives out of the archives'
+    /// removed and added objects. The removed and added objects of the same
+    /// name that are closest in size are paired first.
+    fn find_moves(&self, archives: &mut [ArchiveDiff]) -> Vec<ObjectMove> {
+        // Every pair that could be a move, and the distance between their sizes
+        let mut pairs: Vec<(u128, ObjectAt, ObjectAt)> = Vec::new();
+        for (from_at, from_archive) in archives.iter().enumerate() {
+            for (i, from) in from_archive.removed_objects.iter().enumerate() {
+                for (to_at, to_archive) in archives.iter().enumerate().filter(|&(at, _)| at != from_at) {
+                    for (j, to) in to_archive.added_objects.iter().enumerate() {
+                        if to.key.object == from.key.object && self.similar(&from.row.module, &to.row.module) {
+                            let (l, r) = (every_size(&from.row.module), every_size(&to.row.module));
+                            pairs.push((l.max(r) - l.min(r), (from_at, i), (to_at, j)));
+                        }
+                    }
+                }
+            }
+        }
+        pairs.sort();
+
+        let (mut taken_from, mut taken_to) = (HashSet::new(), HashSet::new());
+        let mut chosen: Vec<(ObjectAt, ObjectAt)> = Vec::new();
+        for (_, from, to) in pairs {
+            if !taken_from.contains(&from) && !taken_to.contains(&to) {
+                taken_from.insert(from);
+                taken_to.insert(to);
+                chosen.push((from, to));
+            }
+        }
+        chosen.sort();
+
+        let moves = chosen
+            .iter()
+            .map(|&((from_at, i), (to_at, j))| {
+                let (from, to) = (&archives[from_at].removed_objects[i], &archives[to_at].added_objects[j]);
+                ObjectMove { from: from.clone(), to: to.clone(), delta: &to.row.module - &from.row.module }
+            })
+            .collect();
+
+        for (at, archive) in archives.iter_mut().enumerate() {
+            archive.removed_objects = untaken(mem::take(&mut archive.removed_objects), at, &taken_from);
+            archive.added_objects = untaken(mem::take(&mut archive.added_objects), at, &taken_to);
+        }
+        moves
+    }
+
+    /// Returns the path that the archive is matched by
+    pub fn normalize(&self, path: &str) -> String {
+        let fold = |path: &str| {
+            let path = if self.unify_separators { path.replace('\\', "/") } else { path.into() };
+            if self.ignore_case { path.to_lowercase() } else { path }
+        };
+
+        let mut path = fold(path);
+        let strips = |prefix: &str| {
+            path.starts_with(prefix)
+                && (prefix.ends_with(is_separator) || path.len() == prefix.len() || path[prefix.len()..].starts_with(is_separator))
+        };
+        if let Some(prefix) = self.prefixes.iter().map(|prefix| fold(prefix)).find(|prefix| strips(prefix)) {
+            path = path[prefix.len()..].trim_start_matches(is_separator).into();
+        }
+        if let Some(components) = self.components {
+            let start = path
+                .rmatch_indices(is_separator)
+                .nth(components - 1)
+                .map_or(0, |(at, separator)| at + separator.len());
+            path = path[start..].into();
+        }
+        path
+    }
+
+    /// Compares the module summaries of two map files
+    pub fn diff(&self, left: &ModuleSummary, right: &ModuleSummary) -> MapDiff {
+        let (larchives, rarchives) = (archives(left, self), archives(right, self));
+
+        let mut diffs: Vec<ArchiveDiff> = Vec::new();
+        for l in &larchives {
+            let r = rarchives.iter().find(|r| r.path == l.path);
+            diffs.push(archive_diff(Some(l), r));
+        }
+        for r in rarchives.iter().filter(|r| !larchives.iter().any(|l| l.path == r.path)) {
+            diffs.push(archive_diff(None, Some(r)));
+        }
+
+        let moved_objects = if self.detect_moves { self.find_moves(&mut diffs) } else { Vec::new() };
+        MapDiff { archives: diffs, moved_objects }
+    }
+}
+
+/// Returns the sum of every size of the module, including the absolute columns
+/// that `Module::total` leaves out, so that moves compare all of an object
+fn every_size(module: &Module) -> u128 {
+    [module.ro_code, module.ro_data, module.rw_data, module.ro_data_abs, module.rw_data_abs]
+        .iter()
+        .map(|size| u128::from(size.unwrap_or(0)))
+        .sum()
+}
+
+/// Returns the objects of the archive at `at` that were not taken by a move
+fn untaken(objects: Vec<ObjectEntry>, at: usize, taken: &HashSet<ObjectAt>) -> Vec<ObjectEntry> {
+    objects
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| !taken.contains(&(at, i)))
+        .map(|(_, object)| object)
+        .collect()
+}
+
+/// Returns true for a path separator
+fn is_separator(c: char) -> bool {
+    c == '\\' || c == '/'
+}
+
+/// A removed or added object of an `ArchiveDiff`, as the index of the archive
+/// and the index of the object
+type ObjectAt = (usize, usize);
+
+/// An object row, keyed by its `ObjectKey` and its occurrence in the archive
+type KeyedRow<'a> = (ObjectKey, usize, &'a ModuleRow);
+
+/// The archives of one map file with the same normalized path, and their rows
+struct Archive<'a> {
+    path: String,
+    entry: ArchiveEntry,
+    rows: Vec<KeyedRow<'a>>,
+}
+
+/// Groups the rows of a summary by normalized archive path, in the order of
+/// the map file. Each row is numbered, so that the nth row of a duplicated
+/// object is matched with the nth row of the same object in the other summary.
+fn archives<'a>(summary: &'a ModuleSummary, options: &DiffOptions) -> Vec<Archive<'a>> {
+    let mut archives: Vec<Archive> = Vec::new();
+    // The rows of each object so far, for each archive
+    let mut counts: Vec<HashMap<&str, usize>> = Vec::new();
+    for table in &summary.tables {
+        let path = options.normalize(&table.path);
+        let at = match archives.iter().position(|archive| archive.path == path) {
+            Some(at) => at,
+            None => {
+                let entry = ArchiveEntry { name: table.name.clone(), line: table.line, module: Module::default() };
+                archives.push(Archive { path, entry, rows: Vec::new() });
+                counts.push(HashMap::new());
+                archives.len() - 1
+            }
+        };
+
+        let archive = &mut archives[at];
+        for row in &table.table {
+            let count = counts[at].entry(row.name.as_str()).or_insert(0);
+            *count += 1;
+            let count = *count;
+            let key = ObjectKey { archive: table.path.clone(), object: row.name.clone() };
+            archive.entry.module += row.module.clone();
+            archive.rows.push((key, count, row));
+        }
+    }
+    archives
+}
+
+/// Looks up rows by their object name and occurrence
+fn rows_by_name<'a>(rows: &'a [KeyedRow<'a>]) -> HashMap<(&'a str, usize), &'a ModuleRow> {
+    rows.iter().map(|(key, count, row)| ((key.object.as_str(), *count), *row)).collect()
+}
+
+/// The rows of one side that are not in the other
+fn unique(rows: &[KeyedRow], other: &HashMap<(&str, usize), &ModuleRow>) -> Vec<ObjectEntry> {
+    rows.iter()
+        .filter(|(key, count, _)| !other.contains_key(&(key.object.as_str(), *count)))
+        .map(|(key, _, row)| ObjectEntry { key: key.clone(), row: (*row).clone() })
+        .collect()
+}
+
+/// Compares an archive across map files
+fn archive_diff(left: Option<&Archive>, right: Option<&Archive>) -> ArchiveDiff {
+    let no_rows: &[KeyedRow] = &[];
+    let lrows = left.map_or(no_rows, |archive| &archive.rows);
+    let rrows = right.map_or(no_rows, |archive| &archive.rows);
+    let (lkeys, rkeys) = (rows_by_name(lrows), rows_by_name(rrows));
+
+    let changed_objects = lrows
+        .iter()
+        .filter_map(|(key, count, l)| {
+            let r = rkeys.get(&(key.object.as_str(), *count))?;
+            if l.module == r.module {
+                return None;
+            }
+            Some(ObjectChange {
+                key: key.clone(),
+                left: (*l).clone(),
+                right: (*r).clone(),
+                delta: &r.module - &l.module,
+            })
+        })
+        .collect();
+
+    let path = left.or(right).map_or(String::new(), |archive| archive.path.clone());
+    let (left, right) = (left.map(|a| a.entry.clone()), right.map(|a| a.entry.clone()));
+    let size = |entry: &Option<ArchiveEntry>| entry.as_ref().map_or(Module::default(), |entry| entry.module.clone());
+    ArchiveDiff {
+        path,
+        delta: size(&right) - size(&left),
+        left,
+        right,
+        removed_objects: unique(lrows, &rkeys),
+        added_objects: unique(rrows, &lkeys),
+        changed_objects,
+    }
+}
+
+/// Compares the module summaries of two map files, matching archives by their
+/// exact path
+pub fn diff(left: &ModuleSummary, right: &ModuleSummary) -> MapDiff {
+    DiffOptions::new().diff(left, right)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use summary::parse_module_summaries;
+
+    static LEFT: &str = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  ro data  rw data
+    ------                         -------  -------  -------
+app: [1]
+    main.o                              40
+    crc.o                               12
+    crc.o                               16
+    --------------------------------------------------------
+    Total:                              68
+
+vendor.a: [2]
+    crc.o                               64
+    --------------------------------------------------------
+    Total:                              64
+
+------------------------------------------------------------
+    Grand Total:                       132
+
+
+*******************************************************************************";
+
+    static RIGHT: &str = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  ro data  rw data
+    ------                         -------  -------  -------
+app: [4]
+    main.o                              48
+    crc.o                               12
+    log.o                               10
+    --------------------------------------------------------
+    Total:                              70
+
+inhouse.a: [2]
+    crc.o                               64
+    --------------------------------------------------------
+    Total:                              64
+
+------------------------------------------------------------
+    Grand Total:                       134
+
+
+*******************************************************************************";
+
+    fn key(archive: &str, object: &str) -> ObjectKey {
+        ObjectKey{ archive: archive.into(), object: object.into() }
+    }
+
+    #[test]
+    fn test_diff() {
+        let left = parse_module_summaries(LEFT.as_bytes()).unwrap();
+        let right = parse_module_summaries(RIGHT.as_bytes()).unwrap();
+        let diff = diff(&left, &right);
+
+        let paths: Vec<&str> = diff.archives.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["app", "vendor.a", "inhouse.a"]);
+
+        let vendor = Module{ ro_code: Some(64), ..Default::default() };
+        assert_eq!(diff.removed_archives(), vec![&ArchiveEntry{ name: "vendor.a: [2]".into(), line: 14, module: vendor.clone() }]);
+        assert_eq!(diff.added_archives(), vec![&ArchiveEntry{ name: "inhouse.a: [2]".into(), line: 14, module: vendor }]);
+        assert_eq!(diff.archives[1].delta, ModuleDelta{ ro_code: Some(-64), ..Default::default() });
+
+        // The app archive is matched despite its new index
+        let app = &diff.archives[0];
+        assert_eq!(app.left.as_ref().unwrap().name, "app: [1]");
+        assert_eq!(app.right.as_ref().unwrap().name, "app: [4]");
+        assert_eq!(app.delta, ModuleDelta{ ro_code: Some(2), ..Default::default() });
+
+        let removed: Vec<(&ObjectKey, usize)> = diff.removed_objects().iter().map(|o| (&o.key, o.row.line)).collect();
+        assert_eq!(removed, vec![(&key("app", "crc.o"), 10)]);
+        let added: Vec<(&ObjectKey, usize)> = diff.added_objects().iter().map(|o| (&o.key, o.row.line)).collect();
+        assert_eq!(added, vec![(&key("app", "log.o"), 10)]);
+
+        assert_eq!(diff.moved_objects.len(), 1);
+        let moved = &diff.moved_objects[0];
+        assert_eq!((&moved.from.key, &moved.to.key), (&key("vendor.a", "crc.o"), &key("inhouse.a", "crc.o")));
+        assert!(moved.delta.is_zero());
+
+        assert_eq!(app.changed_objects.len(), 1);
+        let change = &app.changed_objects[0];
+        assert_eq!(change.key, key("app", "main.o"));
+        assert_eq!(change.delta, ModuleDelta{ ro_code: Some(8), ..Default::default() });
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_moves() {
+        let left = parse_module_summaries(LEFT.as_bytes()).unwrap();
+        let right = parse_module_summaries(RIGHT.as_bytes()).unwrap();
+
+        let diff = DiffOptions::new().detect_moves(false).diff(&left, &right);
+        assert!(diff.moved_objects.is_empty());
+        assert_eq!(diff.removed_objects().len(), 2);
+        assert_eq!(diff.added_objects().len(), 2);
+
+        // Both of the app's crc.o rows could have moved to inhouse.a with a
+        // large enough tolerance, but the vendor.a crc.o is the closest in size
+        let grown = LEFT.replace("    crc.o                               64", "    crc.o                               80");
+        let left = parse_module_summaries(grown.as_bytes()).unwrap();
+        assert_eq!(moved_sizes(&DiffOptions::new(), &left, &right), vec![(80, 64)]);
+        assert_eq!(moved_sizes(&DiffOptions::new().move_tolerance(10), &left, &right), vec![]);
+        assert_eq!(moved_sizes(&DiffOptions::new().move_tolerance(100), &left, &right), vec![(80, 64)]);
+    }
+
+    /// Returns the left and right size of each moved object
+    fn moved_sizes(options: &DiffOptions, left: &ModuleSummary, right: &ModuleSummary) -> Vec<(u64, u64)> {
+        let diff = options.diff(left, right);
+        diff.moved_objects.iter().map(|m| (m.from.row.module.total(), m.to.row.module.total())).collect()
+    }
+
+    #[test]
+    fn test_diff_changes() {
+        let left = parse_module_summaries(LEFT.as_bytes()).unwrap();
+        let right = parse_module_summaries(RIGHT.as_bytes()).unwrap();
+        let changes = diff(&left, &right).changes();
+
+        let bytes: Vec<(&str, i64, Option<f64>)> = changes
+            .iter()
+            .map(|c| (c.key.object.as_str(), c.bytes(Measure::RoCode), c.percent(Measure::RoCode)))
+            .collect();
+        assert_eq!(bytes, vec![
+            ("crc.o", -16, Some(-100.0)),
+            ("log.o", 10, None),
+            ("main.o", 8, Some(20.0)),
+            ("crc.o", 0, Some(0.0)),
+        ]);
+        assert_eq!(changes[3].key, key("inhouse.a", "crc.o"));
+        assert_eq!(changes[2].bytes(Measure::RwData), 0);
+    }
+
+    #[test]
+    fn test_abs_columns() {
+        let from = Module{ ro_code: Some(100), ro_data_abs: Some(400), ..Default::default() };
+        let to = Module{ ro_code: Some(100), ..Default::default() };
+        assert!(!DiffOptions::new().similar(&from, &to));
+        assert!(DiffOptions::new().similar(&from, &from));
+
+        let delta = &to - &from;
+        assert_eq!(Measure::RoDataAbs.size(&from), 400);
+        assert_eq!(Measure::RoDataAbs.change(&delta), -400);
+        assert_eq!(Measure::RwDataAbs.change(&delta), 0);
+        assert_eq!(Measure::Total.change(&delta), 0);
+    }
+
+    #[test]
+    fn test_similar_tolerance() {
+        let from = Module{ ro_code: Some(u64::MAX), ro_data: Some(u64::MAX), ..Default::default() };
+        let to = Module{ ro_code: Some(1), ..Default::default() };
+        assert!(DiffOptions::new().move_tolerance(u64::MAX).similar(&from, &to));
+        assert!(!DiffOptions::new().move_tolerance(99).similar(&from, &to));
+    }
+
+    #[test]
+    fn test_diff_same() {
+        let left = parse_module_summaries(LEFT.as_bytes()).unwrap();
+        assert!(diff(&left, &left).is_empty());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let exact = DiffOptions::new();
+        assert_eq!(exact.normalize(r"C:\!prj\Foo\Obj"), r"C:\!prj\Foo\Obj");
+
+        let prefixed = DiffOptions::new().strip_prefix(r"C:\alice").strip_prefix(r"D:\bob");
+        assert_eq!(prefixed.normalize(r"C:\alice\Foo\Obj"), r"Foo\Obj");
+        assert_eq!(prefixed.normalize(r"D:\bob\Foo\Obj"), r"Foo\Obj");
+        assert_eq!(prefixed.normalize(r"d:\bob\Foo\Obj"), r"d:\bob\Foo\Obj");
+        assert_eq!(prefixed.normalize(r"C:\alice-7\Foo\Obj"), r"C:\alice-7\Foo\Obj");
+        assert_eq!(prefixed.normalize(r"C:\alice"), "");
+        assert_eq!(DiffOptions::new().strip_prefix("/home/").normalize("/home/ci/Obj"), "ci/Obj");
+
+        let trailing = DiffOptions::new().trailing_components(2);
+        assert_eq!(trailing.normalize(r"C:\!prj\Foo\Obj"), r"Foo\Obj");
+        assert_eq!(trailing.normalize("/home/ci/Foo/Obj"), "Foo/Obj");
+        assert_eq!(trailing.normalize("dl7M_tln.a"), "dl7M_tln.a");
+
+        let windows = DiffOptions::new().ignore_case(true).unify_separators(true);
+        assert_eq!(windows.normalize(r"C:\!prj\Foo\Obj"), "c:/!prj/foo/obj");
+    }
+
+    #[test]
+    fn test_trailing_components_zero() {
+        let whole = DiffOptions::new().trailing_components(2).trailing_components(0);
+        assert_eq!(whole.normalize(r"C:\!prj\Foo\Obj"), r"C:\!prj\Foo\Obj");
+    }
+
+    #[test]
+    fn test_diff_options() {
+        let checkout = |root: &str| {
+            LEFT.replace("app: [1]", &format!(r"{}\App\Obj: [1]", root))
+        };
+        let left = parse_module_summaries(checkout(r"C:\alice").as_bytes()).unwrap();
+        let right = parse_module_summaries(checkout(r"c:\ci\AGENT").as_bytes()).unwrap();
+
+        assert_eq!(diff(&left, &right).added_archives().len(), 1);
+
+        let options = DiffOptions::new().ignore_case(true).strip_prefix(r"C:\alice").strip_prefix(r"C:\ci\agent");
+        let diff = options.diff(&left, &right);
+        assert!(diff.is_empty(), "{:?}", diff);
+        assert_eq!(diff.archives[0].path, r"app\obj");
+    }
+}
@@ -0,0 +1,268 @@
+//! The "entry list" library module
+
+use nom::*;
+
+use error::Error;
+use section::{column_header, linep, parse_hex, section_start, skip_blank_lines, split_archive_index, words, Warnings};
+
+use std::str;
+
+/// The title of the entry list section
+const SECTION: &str = "ENTRY LIST";
+
+/// The type of a symbol. The linker shows `--` for symbols that are neither
+/// code nor data, such as section boundaries.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SymbolKind {
+    Code,
+    Data,
+}
+
+/// The binding of a symbol: `Gb`, `Lc`, or `Wk`
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Binding {
+    Global,
+    Local,
+    Weak,
+}
+
+/// A symbol from the entry list.
+///
+/// For an entry list
+///
+/// ```text
+/// Entry                      Address    Size  Type      Object
+/// -----                      -------    ----  ----      ------
+/// .iar.dynexit$$Base      0x20004294           --   Gb  - Linker created -
+/// HAL_Init                0x08000c81    0x2a  Code  Gb  stm32_hal.o [4]
+/// ```
+///
+/// `HAL_Init` has an `address` of `0x08000c81`, a `size` of `Some(0x2a)`, a
+/// `kind` of `Some(SymbolKind::Code)`, a `Binding::Global` `binding`, an
+/// `object` of `stm32_hal.o`, and an `archive` of `Some(4)`.
+/// `.iar.dynexit$$Base` has no size, no kind, and no archive.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u64,
+    pub size: Option<u64>,
+    pub kind: Option<SymbolKind>,
+    pub binding: Binding,
+    pub object: String,
+    pub archive: Option<usize>,
+}
+
+/// Parses the `Type` column of a row
+fn kindp(word: &str) -> Option<Option<SymbolKind>> {
+    match word {
+        "Code" => Some(Some(SymbolKind::Code)),
+        "Data" => Some(Some(SymbolKind::Data)),
+        "--" => Some(None),
+        _ => None,
+    }
+}
+
+/// Parses the binding column of a row
+fn bindingp(word: &str) -> Option<Binding> {
+    match word {
+        "Gb" => Some(Binding::Global),
+        "Lc" => Some(Binding::Local),
+        "Wk" => Some(Binding::Weak),
+        _ => None,
+    }
+}
+
+/// Parses a row of the entry list. The columns are found by their content
+/// rather than their position: the name is everything before the address,
+/// and the object is everything after the binding.
+fn symbolp(row: &str) -> Option<Symbol> {
+    let words = words(row);
+    let at = words.iter().skip(1).position(|&(_, w)| parse_hex(w).is_some())? + 1;
+    let mut rest = words[at..].iter();
+
+    let address = parse_hex(rest.next()?.1)?;
+    let mut next = rest.next()?.1;
+    let size = parse_hex(next);
+    if size.is_some() {
+        next = rest.next()?.1;
+    }
+    let kind = kindp(next)?;
+    let binding = bindingp(rest.next()?.1)?;
+    let (object, archive) = split_archive_index(&row[rest.next()?.0..]);
+
+    Some(Symbol {
+        name: row[..words[at].0].trim().into(),
+        address,
+        size,
+        kind,
+        binding,
+        object: object.into(),
+        archive,
+    })
+}
+
+/// Parses the entry list from an IAR map file
+pub fn parse_entries(input: &[u8]) -> Result<Vec<Symbol>, Error> {
+    parse_entries_with(input, &mut Warnings::strict())
+}
+
+/// Parses the entry list, reporting each malformed row to the warnings
+pub fn parse_entries_with(input: &[u8], warnings: &mut Warnings) -> Result<Vec<Symbol>, Error> {
+    let rest = match section_start(input, SECTION) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::MissingSection(SECTION)),
+    };
+
+    let mut rest = match column_header(rest, "Entry") {
+        IResult::Done(rest, _) => rest,
+        _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+    };
+
+    let mut symbols = Vec::new();
+    while let IResult::Done(after, row) = linep(rest) {
+        if row.iter().all(|c| c.is_ascii_whitespace()) {
+            break;
+        }
+        let text = match str::from_utf8(row) {
+            Ok(text) => text,
+            Err(_) => {
+                warnings.malformed_row(SECTION, input, rest)?;
+                rest = after;
+                continue;
+            }
+        };
+
+        // A name that is too long for its column is alone on its line, and the
+        // rest of the row is on the next line. A malformed row skips only its
+        // own line.
+        let (after, symbol) = match symbolp(text) {
+            Some(symbol) => (after, Some(symbol)),
+            None if words(text).len() == 1 => match linep(after) {
+                IResult::Done(joined_after, next) => {
+                    let joined = str::from_utf8(next).map(|next| format!("{} {}", text.trim(), next.trim_start()));
+                    match joined.ok().and_then(|joined| symbolp(&joined)) {
+                        Some(symbol) => (joined_after, Some(symbol)),
+                        None => (after, None),
+                    }
+                }
+                _ => (after, None),
+            },
+            None => (after, None),
+        };
+
+        match symbol {
+            Some(symbol) => symbols.push(symbol),
+            None => warnings.malformed_row(SECTION, input, rest)?,
+        }
+        rest = after;
+    }
+
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_symbolp() {
+        let row = "HAL_Init                0x0800'0c81    0x2a  Code  Gb  stm32_hal.o [4]";
+        let expected = Symbol {
+            name: "HAL_Init".into(),
+            address: 0x0800_0c81,
+            size: Some(0x2a),
+            kind: Some(SymbolKind::Code),
+            binding: Binding::Global,
+            object: "stm32_hal.o".into(),
+            archive: Some(4),
+        };
+        assert_eq!(symbolp(row), Some(expected));
+    }
+
+    #[test]
+    fn test_symbolp_without_size_or_kind() {
+        let row = ".iar.dynexit$$Base      0x20004294           --   Gb  - Linker created -";
+        let expected = Symbol {
+            name: ".iar.dynexit$$Base".into(),
+            address: 0x2000_4294,
+            size: None,
+            kind: None,
+            binding: Binding::Global,
+            object: "- Linker created -".into(),
+            archive: None,
+        };
+        assert_eq!(symbolp(row), Some(expected));
+    }
+
+    #[test]
+    fn test_symbolp_malformed() {
+        assert_eq!(symbolp("?main                   0x080e499d          Code  Xx  cmain.o [7]"), None);
+        assert_eq!(symbolp("?main                   0x080e499d          Code  Gb"), None);
+        assert_eq!(symbolp("Here's some text"), None);
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let input = "*******************************************************************************
+*** ENTRY LIST
+***
+
+Entry                      Address    Size  Type      Object
+-----                      -------    ----  ----      ------
+.iar.dynexit$$Base      0x20004294           --   Gb  - Linker created -
+?main                   0x080e499d          Code  Gb  cmain.o [7]
+__iar_a_very_long_function_name_that_wraps
+                        0x080e4a01    0x1c  Code  Lc  wraps.o [2]
+SystemCoreClock         0x20000000     0x4  Data  Wk  system.o [1]
+
+[1] = C:\\Projects\\A\\Obj
+"
+            .as_bytes();
+
+        let symbols = parse_entries(input).unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                ".iar.dynexit$$Base",
+                "?main",
+                "__iar_a_very_long_function_name_that_wraps",
+                "SystemCoreClock",
+            ]
+        );
+        assert_eq!(symbols[2].address, 0x080e_4a01);
+        assert_eq!(symbols[2].size, Some(0x1c));
+        assert_eq!(symbols[2].binding, Binding::Local);
+        assert_eq!(symbols[3].kind, Some(SymbolKind::Data));
+        assert_eq!(symbols[3].object, "system.o");
+        assert_eq!(symbols[3].archive, Some(1));
+    }
+
+    #[test]
+    fn test_parse_entries_malformed_row() {
+        let input = "*******************************************************************************
+*** ENTRY LIST
+***
+
+Entry                      Address    Size  Type      Object
+-----                      -------    ----  ----      ------
+?main                   0x080e499d          Code  Gb  cmain.o [7]
+?broken                 0x080e499d          Cake  Gb  cmain.o [7]
+"
+            .as_bytes();
+
+        match parse_entries(input) {
+            Err(Error::MalformedRow { section: "ENTRY LIST", line: 8, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_entries_missing() {
+        match parse_entries(b"*** MODULE SUMMARY\n") {
+            Err(Error::MissingSection("ENTRY LIST")) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Errors reported while parsing a map file
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// The ways that parsing a map file can fail.
+///
+/// Errors that relate to the content of a section carry the section's title,
+/// such as `MODULE SUMMARY`, the 1-based line number, and the text of the
+/// offending line.
+#[derive(Debug)]
+pub enum Error {
+    /// The map file could not be read
+    Io(io::Error),
+    /// The map file does not start with the linker's `#` banner
+    MissingHeader,
+    /// The map file has no section with the title
+    MissingSection(&'static str),
+    /// The line following the section header is not the expected column header
+    MalformedColumnHeader { section: &'static str, line: usize, text: String },
+    /// A row of the section could not be parsed
+    MalformedRow { section: &'static str, line: usize, text: String },
+    /// The section has a column header that the parser does not support
+    UnsupportedLayout { section: &'static str, line: usize, text: String },
+}
+
+impl Error {
+    /// Returns the 1-based line number and text of the line that starts `rest`,
+    /// a suffix of `input`.
+    pub(crate) fn locate(input: &[u8], rest: &[u8]) -> (usize, String) {
+        let offset = input.len() - rest.len();
+        let line = input[..offset].iter().filter(|c| **c == b'\n').count() + 1;
+        (line, Error::line_text(rest))
+    }
+
+    /// Returns the text of the line that starts `rest`, without its line ending
+    fn line_text(rest: &[u8]) -> String {
+        let end = rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+        String::from_utf8_lossy(&rest[..end]).trim_end().into()
+    }
+
+    /// A `MalformedColumnHeader` for the line that starts `rest`
+    pub(crate) fn malformed_column_header(section: &'static str, input: &[u8], rest: &[u8]) -> Error {
+        let (line, text) = Error::locate(input, rest);
+        Error::MalformedColumnHeader { section, line, text }
+    }
+
+    /// A `MalformedRow` for the line that starts `rest`
+    pub(crate) fn malformed_row(section: &'static str, input: &[u8], rest: &[u8]) -> Error {
+        let (line, text) = Error::locate(input, rest);
+        Error::MalformedRow { section, line, text }
+    }
+
+    /// A `MalformedRow` for the line that starts `rest`, when its 1-based line
+    /// number is already known
+    pub(crate) fn malformed_row_at(section: &'static str, line: usize, rest: &[u8]) -> Error {
+        Error::MalformedRow { section, line, text: Error::line_text(rest) }
+    }
+
+    /// An `UnsupportedLayout` for the line that starts `rest`
+    pub(crate) fn unsupported_layout(section: &'static str, input: &[u8], rest: &[u8]) -> Error {
+        let (line, text) = Error::locate(input, rest);
+        Error::UnsupportedLayout { section, line, text }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "failed to read the map file: {}", err),
+            Error::MissingHeader => write!(f, "no linker banner at the start of the map file"),
+            Error::MissingSection(section) => write!(f, "no {} section in the map file", section),
+            Error::MalformedColumnHeader { section, line, ref text } => {
+                write!(f, "line {}: malformed {} column header: {}", line, section, text)
+            }
+            Error::MalformedRow { section, line, ref text } => {
+                write!(f, "line {}: malformed {} row: {}", line, section, text)
+            }
+            Error::UnsupportedLayout { section, line, ref text } => {
+                write!(f, "line {}: unsupported {} layout: {}", line, section, text)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let input = b"first\nsecond\n  third row  \nfourth";
+        let rest = &input[13..];
+        assert_eq!(Error::locate(input, rest), (3, "  third row".into()));
+    }
+
+    #[test]
+    fn test_locate_last_line() {
+        let input = b"first\nsecond";
+        let rest = &input[6..];
+        assert_eq!(Error::locate(input, rest), (2, "second".into()));
+    }
+}
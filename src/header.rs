@@ -0,0 +1,292 @@
+//! The map file header library module
+
+use nom::*;
+
+use error::Error;
+
+use std::fmt;
+use std::str;
+
+/// The banner at the top of a map file.
+///
+/// For a banner
+///
+/// ```text
+/// ###############################################################################
+/// #
+/// # IAR ELF Linker V8.32.1.169/W32 for ARM                  26/Mar/2019  14:37:57
+/// # Copyright 2007-2018 IAR Systems AB.
+/// #
+/// #    Output file  =  C:\Projects\A\Exe\app.out
+/// #    Map file     =  C:\Projects\A\List\app.map
+/// #    Command line =
+/// #        -f C:\Temp\EW5F0.tmp (C:\Projects\A\Obj\main.o -o
+/// #        C:\Projects\A\Exe\app.out --map C:\Projects\A\List\app.map)
+/// #
+/// ###############################################################################
+/// ```
+///
+/// the `linker` member is `IAR ELF Linker`, the `version` member is
+/// `8.32.1.169`, and the `target` member is `ARM`. The `timestamp` member is
+/// the date and time exactly as the linker wrote them, `26/Mar/2019  14:37:57`.
+/// The wrapped lines of the command line are joined with single spaces.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MapHeader {
+    pub linker: String,
+    pub version: Option<String>,
+    pub target: Option<String>,
+    pub timestamp: Option<String>,
+    pub output_file: Option<String>,
+    pub map_file: Option<String>,
+    pub command_line: Option<String>,
+}
+
+impl MapHeader {
+    /// Returns true if both headers name the same linker and linker version
+    pub fn same_linker(&self, other: &MapHeader) -> bool {
+        self.linker == other.linker && self.version == other.version
+    }
+}
+
+impl fmt::Display for MapHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.linker)?;
+        if let Some(ref version) = self.version {
+            write!(f, " V{}", version)?;
+        }
+        if let Some(ref target) = self.target {
+            write!(f, " for {}", target)?;
+        }
+        if let Some(ref timestamp) = self.timestamp {
+            write!(f, " ({})", timestamp)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses one line of the banner, returning the text after the leading '#'
+fn banner_line(input: &[u8]) -> IResult<&[u8], &str> {
+    map_res!(input,
+        do_parse!(
+            char!('#') >>
+            text: not_line_ending >>
+            opt!(complete!(line_ending)) >>
+            (text)
+        ),
+        str::from_utf8
+    )
+}
+
+/// Parses the banner, returning the text of each line
+fn banner(input: &[u8]) -> IResult<&[u8], Vec<&str>> {
+    many1!(input, banner_line)
+}
+
+/// Removes the '#' border and padding from the text of a banner line
+fn unframe(text: &str) -> &str {
+    let text = text.trim_end();
+    let text = if text.ends_with(" #") || text == "#" {
+        &text[..text.len() - 1]
+    } else {
+        text
+    };
+    text.trim()
+}
+
+/// Returns true if the word looks like a `V8.32.1.169/W32` version
+fn is_version(word: &str) -> bool {
+    word.starts_with('V') && word[1..].starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Returns true if the words look like a `26/Mar/2019  14:37:57` timestamp
+fn is_timestamp(date: &str, time: &str) -> bool {
+    date.split('/').count() == 3 && time.split(':').count() == 3
+}
+
+/// Parses the first line of the banner into the linker, version, target, and
+/// timestamp of the header
+fn parse_title(title: &str, header: &mut MapHeader) {
+    let mut words: Vec<&str> = title.split_whitespace().collect();
+
+    let len = words.len();
+    if len >= 2 && is_timestamp(words[len - 2], words[len - 1]) {
+        let start = title.rfind(words[len - 2]).unwrap_or(0);
+        header.timestamp = Some(title[start..].into());
+        words.truncate(len - 2);
+    }
+
+    if let Some(pos) = words.iter().position(|w| is_version(w)) {
+        let version = words[pos][1..].split('/').next().unwrap_or("");
+        header.version = Some(version.into());
+        if words.get(pos + 1) == Some(&"for") && words.len() > pos + 2 {
+            header.target = Some(words[pos + 2..].join(" "));
+        }
+        words.truncate(pos);
+    }
+
+    header.linker = words.join(" ");
+}
+
+/// Splits a `key = value` line of the banner into its trimmed key and value.
+/// The value may be empty if it continues on the following lines.
+fn split_field(line: &str) -> Option<(&str, &str)> {
+    let eq = if line.ends_with(" =") {
+        line.len() - 2
+    } else {
+        line.find(" = ")?
+    };
+    Some((line[..eq].trim(), line[eq + 2..].trim()))
+}
+
+/// Parses the map file header from the banner at the top of an IAR map file
+pub fn parse_banner(input: &[u8]) -> Result<MapHeader, Error> {
+    let lines = match banner(input) {
+        IResult::Done(_, lines) => lines,
+        _ => return Err(Error::MissingHeader),
+    };
+
+    let mut lines = lines
+        .into_iter()
+        .map(unframe)
+        .filter(|line| !line.is_empty() && !line.chars().all(|c| c == '#'));
+
+    let mut header = MapHeader::default();
+    match lines.next() {
+        Some(title) => parse_title(title, &mut header),
+        None => return Err(Error::MissingHeader),
+    }
+
+    // The fields that follow are "key = value", where a value may continue on
+    // the following lines
+    let mut current: Option<&mut Option<String>> = None;
+    for line in lines {
+        if let Some((key, value)) = split_field(line) {
+            let field = match key {
+                "Output file" => &mut header.output_file,
+                "Map file" => &mut header.map_file,
+                "Command line" => &mut header.command_line,
+                _ => {
+                    current = None;
+                    continue;
+                }
+            };
+            *field = if value.is_empty() { None } else { Some(value.into()) };
+            current = Some(field);
+        } else if let Some(ref mut field) = current {
+            let joined = match field.take() {
+                Some(value) => format!("{} {}", value, line),
+                None => line.into(),
+            };
+            **field = Some(joined);
+        }
+    }
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_unframe() {
+        assert_eq!(unframe("    Map file     =  C:\\app.map     #"), "Map file     =  C:\\app.map");
+        assert_eq!(unframe(" IAR ELF Linker V8.32.1.169/W32 for ARM"), "IAR ELF Linker V8.32.1.169/W32 for ARM");
+        assert_eq!(unframe("                       #"), "");
+    }
+
+    #[test]
+    fn test_split_field() {
+        assert_eq!(split_field("Map file     =  C:\\app.map"), Some(("Map file", "C:\\app.map")));
+        assert_eq!(split_field("Command line ="), Some(("Command line", "")));
+        assert_eq!(split_field("--redirect _Printf=_PrintfFull"), None);
+    }
+
+    #[test]
+    fn test_parse_banner() {
+        let input = "###############################################################################
+#
+# IAR ELF Linker V8.32.1.169/W32 for ARM                  26/Mar/2019  14:37:57
+# Copyright 2007-2018 IAR Systems AB.
+#
+#    Output file  =  C:\\Projects\\A\\Exe\\app.out
+#    Map file     =  C:\\Projects\\A\\List\\app.map
+#    Command line =
+#        -f C:\\Temp\\EW5F0.tmp (C:\\Projects\\A\\Obj\\main.o -o
+#        C:\\Projects\\A\\Exe\\app.out --map C:\\Projects\\A\\List\\app.map)
+#
+###############################################################################
+
+*******************************************************************************"
+            .as_bytes();
+
+        let expected = MapHeader {
+            linker: "IAR ELF Linker".into(),
+            version: Some("8.32.1.169".into()),
+            target: Some("ARM".into()),
+            timestamp: Some("26/Mar/2019  14:37:57".into()),
+            output_file: Some("C:\\Projects\\A\\Exe\\app.out".into()),
+            map_file: Some("C:\\Projects\\A\\List\\app.map".into()),
+            command_line: Some(
+                "-f C:\\Temp\\EW5F0.tmp (C:\\Projects\\A\\Obj\\main.o -o \
+                 C:\\Projects\\A\\Exe\\app.out --map C:\\Projects\\A\\List\\app.map)"
+                    .into(),
+            ),
+        };
+
+        assert_eq!(parse_banner(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_framed_banner() {
+        let input = "###############################################################################
+#                                                                             #
+# IAR ELF Linker V7.40.3.8902/W32 for ARM               22/Nov/2016  10:18:24 #
+# Copyright 2007-2015 IAR Systems AB.                                         #
+#                                                                             #
+#    Output file  =  C:\\A\\app.out                                            #
+#    Map file     =  C:\\A\\app.map                                            #
+#    Command line =  C:\\A\\main.o -o C:\\A\\app.out                            #
+#                    --map C:\\A\\app.map                                      #
+#                                                                             #
+###############################################################################
+"
+            .as_bytes();
+
+        let header = parse_banner(input).unwrap();
+        assert_eq!(header.linker, "IAR ELF Linker");
+        assert_eq!(header.version, Some("7.40.3.8902".into()));
+        assert_eq!(header.timestamp, Some("22/Nov/2016  10:18:24".into()));
+        assert_eq!(header.output_file, Some("C:\\A\\app.out".into()));
+        assert_eq!(header.command_line, Some("C:\\A\\main.o -o C:\\A\\app.out --map C:\\A\\app.map".into()));
+    }
+
+    #[test]
+    fn test_parse_banner_missing() {
+        match parse_banner(b"*** MODULE SUMMARY\n") {
+            Err(Error::MissingHeader) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let header = MapHeader {
+            linker: "IAR ELF Linker".into(),
+            version: Some("8.32.1.169".into()),
+            target: Some("ARM".into()),
+            timestamp: Some("26/Mar/2019  14:37:57".into()),
+            ..Default::default()
+        };
+        assert_eq!(format!("{}", header), "IAR ELF Linker V8.32.1.169 for ARM (26/Mar/2019  14:37:57)");
+    }
+
+    #[test]
+    fn test_same_linker() {
+        let a = MapHeader{ linker: "IAR ELF Linker".into(), version: Some("8.32.1.169".into()), ..Default::default() };
+        let b = MapHeader{ version: Some("8.40.1.212".into()), ..a.clone() };
+        assert!(a.same_linker(&a.clone()));
+        assert!(!a.same_linker(&b));
+    }
+}
@@ -0,0 +1,311 @@
+//! The "init table" library module
+
+use nom::*;
+
+use error::Error;
+use section::{column_header, linep, parse_hex, section_start, skip_blank_lines, words, Warnings};
+
+use std::str;
+
+/// The title of the init table section
+const SECTION: &str = "INIT TABLE";
+
+/// How an init table entry initializes its destination
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InitKind {
+    /// Fills the destination with zeros
+    Zero,
+    /// Copies the source, in flash, to the destination
+    Copy,
+    /// Decompresses the source to the destination. The method is the text
+    /// after `Copy/`, such as `packed` or `lz77`.
+    CompressedCopy(String),
+    /// Calls a routine, such as the C++ static constructors
+    Extra,
+}
+
+impl InitKind {
+    /// Returns the kind with the text that starts an entry
+    fn from_text(text: &str) -> Option<InitKind> {
+        match text {
+            "Zero" => Some(InitKind::Zero),
+            "Copy" => Some(InitKind::Copy),
+            "Extra" => Some(InitKind::Extra),
+            _ if text.starts_with("Copy/") => Some(InitKind::CompressedCopy(text[5..].into())),
+            _ => None,
+        }
+    }
+}
+
+/// A range of addresses listed by an init table entry
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct InitRange {
+    pub address: u64,
+    pub size: u64,
+}
+
+/// An entry of the init table.
+///
+/// For an entry
+///
+/// ```text
+/// Copy (__iar_copy_init3)
+///     1 source range, total size 0x8:
+///            0x800'1020     0x8
+///     1 destination range, total size 0x8:
+///           0x2000'0000     0x8
+/// ```
+///
+/// the `kind` is `InitKind::Copy`, and the `routine` is `__iar_copy_init3`.
+/// Zero entries have no `source` ranges, and `Extra` entries have no ranges.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InitEntry {
+    pub kind: InitKind,
+    pub routine: String,
+    pub source: Vec<InitRange>,
+    pub destination: Vec<InitRange>,
+}
+
+/// Sums the sizes, saturating at `u64::MAX` rather than overflowing on a
+/// corrupt table
+fn saturating_sum<I: Iterator<Item = u64>>(sizes: I) -> u64 {
+    sizes.fold(0, u64::saturating_add)
+}
+
+impl InitEntry {
+    /// Returns the total size of the source ranges, saturating at `u64::MAX`
+    pub fn source_size(&self) -> u64 {
+        saturating_sum(self.source.iter().map(|range| range.size))
+    }
+
+    /// Returns the total size of the destination ranges, saturating at
+    /// `u64::MAX`
+    pub fn destination_size(&self) -> u64 {
+        saturating_sum(self.destination.iter().map(|range| range.size))
+    }
+}
+
+/// The init table of a map file
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct InitTable {
+    pub entries: Vec<InitEntry>,
+}
+
+impl InitTable {
+    /// Returns the bytes of RAM that are zero-initialized at startup. Each of
+    /// the sizes saturates at `u64::MAX`.
+    pub fn zero_initialized(&self) -> u64 {
+        saturating_sum(
+            self.entries
+                .iter()
+                .filter(|entry| entry.kind == InitKind::Zero)
+                .map(InitEntry::destination_size),
+        )
+    }
+
+    /// Returns the bytes of RAM that are copied, or decompressed, from flash at
+    /// startup
+    pub fn copied(&self) -> u64 {
+        saturating_sum(
+            self.entries
+                .iter()
+                .filter(|entry| matches!(entry.kind, InitKind::Copy | InitKind::CompressedCopy(_)))
+                .map(InitEntry::destination_size),
+        )
+    }
+
+    /// Returns the bytes of flash that hold the initializers of copied RAM
+    pub fn initializers(&self) -> u64 {
+        saturating_sum(self.entries.iter().map(InitEntry::source_size))
+    }
+}
+
+/// Parses the first line of an entry, such as `Zero (__iar_zero_init3)`
+fn entryp(row: &str) -> Option<InitEntry> {
+    let open = row.find(" (")?;
+    if !row.ends_with(')') {
+        return None;
+    }
+    Some(InitEntry {
+        kind: InitKind::from_text(&row[..open])?,
+        routine: row[open + 2..row.len() - 1].into(),
+        source: Vec::new(),
+        destination: Vec::new(),
+    })
+}
+
+/// Parses a `1 source range, total size 0x8:` line, returning true for source
+/// ranges and false for destination ranges
+fn range_headerp(row: &str) -> Option<bool> {
+    if !row.contains("total size") {
+        return None;
+    }
+    match words(row).get(1).map(|&(_, word)| word) {
+        Some("source") => Some(true),
+        Some("destination") => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses a `0x2000'0000     0x8` range
+fn rangep(row: &str) -> Option<InitRange> {
+    match words(row)[..] {
+        [(_, address), (_, size)] => Some(InitRange {
+            address: parse_hex(address)?,
+            size: parse_hex(size)?,
+        }),
+        _ => None,
+    }
+}
+
+/// Parses the init table from an IAR map file
+pub fn parse_init_table(input: &[u8]) -> Result<InitTable, Error> {
+    parse_init_table_with(input, &mut Warnings::strict())
+}
+
+/// Parses the init table, reporting each malformed row to the warnings
+pub fn parse_init_table_with(input: &[u8], warnings: &mut Warnings) -> Result<InitTable, Error> {
+    let mut rest = match section_start(input, SECTION) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::MissingSection(SECTION)),
+    };
+
+    rest = match column_header(rest, "Address") {
+        IResult::Done(rest, _) => rest,
+        _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+    };
+
+    let mut table = InitTable::default();
+    let mut source = false;
+    while let IResult::Done(after, line) = linep(rest) {
+        if rest.is_empty() || line.starts_with(b"*") {
+            break;
+        }
+        let (row, text) = (rest, str::from_utf8(line));
+        rest = after;
+        let text = match text {
+            Ok(text) => text,
+            Err(_) => {
+                warnings.malformed_row(SECTION, input, row)?;
+                continue;
+            }
+        };
+
+        if text.trim().is_empty() {
+            // Blank lines separate the entries
+        } else if !text.starts_with(char::is_whitespace) {
+            match entryp(text.trim_end()) {
+                Some(entry) => table.entries.push(entry),
+                None => warnings.malformed_row(SECTION, input, row)?,
+            }
+        } else if let Some(is_source) = range_headerp(text) {
+            source = is_source;
+        } else {
+            match (rangep(text), table.entries.last_mut()) {
+                (Some(range), Some(entry)) if source => entry.source.push(range),
+                (Some(range), Some(entry)) => entry.destination.push(range),
+                _ => warnings.malformed_row(SECTION, input, row)?,
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    static TABLE: &str = "*******************************************************************************
+*** INIT TABLE
+***
+
+          Address      Size
+          -------      ----
+Zero (__iar_zero_init3)
+    1 destination range, total size 0x1c4c:
+          0x2000'0008  0x1c4c
+
+Copy/packed (__iar_packbits_init3)
+    1 source range, total size 0x25 (42% of destination):
+           0x800'1020    0x25
+    2 destination ranges, total size 0x58:
+          0x2000'0000    0x50
+          0x2000'1c54     0x8
+
+Extra (__iar_cstart_call_ctors)
+
+
+*******************************************************************************
+*** MODULE SUMMARY
+***
+";
+
+    #[test]
+    fn test_entryp() {
+        let entry = entryp("Copy/lz77 (__iar_lz77_init3)").unwrap();
+        assert_eq!(entry.kind, InitKind::CompressedCopy("lz77".into()));
+        assert_eq!(entry.routine, "__iar_lz77_init3");
+        assert_eq!(entryp("Zero"), None);
+        assert_eq!(entryp("Fill (__iar_fill_init)"), None);
+    }
+
+    #[test]
+    fn test_range_headerp() {
+        assert_eq!(range_headerp("    1 source range, total size 0x25 (42% of destination):"), Some(true));
+        assert_eq!(range_headerp("    2 destination ranges, total size 0x58:"), Some(false));
+        assert_eq!(range_headerp("          0x2000'0000    0x50"), None);
+    }
+
+    #[test]
+    fn test_parse_init_table() {
+        let table = parse_init_table(TABLE.as_bytes()).unwrap();
+        assert_eq!(table.entries.len(), 3);
+
+        let zero = &table.entries[0];
+        assert_eq!(zero.kind, InitKind::Zero);
+        assert_eq!(zero.source, vec![]);
+        assert_eq!(zero.destination, vec![InitRange{ address: 0x2000_0008, size: 0x1c4c }]);
+
+        let packed = &table.entries[1];
+        assert_eq!(packed.kind, InitKind::CompressedCopy("packed".into()));
+        assert_eq!(packed.source, vec![InitRange{ address: 0x0800_1020, size: 0x25 }]);
+        assert_eq!(packed.destination.len(), 2);
+
+        assert_eq!(table.entries[2].kind, InitKind::Extra);
+        assert_eq!(table.entries[2].destination, vec![]);
+
+        assert_eq!(table.zero_initialized(), 0x1c4c);
+        assert_eq!(table.copied(), 0x58);
+        assert_eq!(table.initializers(), 0x25);
+    }
+
+    #[test]
+    fn test_saturating_sizes() {
+        let huge = InitRange { address: 0, size: u64::MAX };
+        let entry = |kind| InitEntry { kind, routine: String::new(), source: vec![huge, huge], destination: vec![huge, huge] };
+        let table = InitTable { entries: vec![entry(InitKind::Zero), entry(InitKind::Zero), entry(InitKind::Copy)] };
+        assert_eq!(table.entries[0].source_size(), u64::MAX);
+        assert_eq!(table.zero_initialized(), u64::MAX);
+        assert_eq!(table.copied(), u64::MAX);
+        assert_eq!(table.initializers(), u64::MAX);
+    }
+
+    #[test]
+    fn test_parse_init_table_missing() {
+        match parse_init_table(b"*** MODULE SUMMARY\n***\n") {
+            Err(Error::MissingSection("INIT TABLE")) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_init_table_malformed_row() {
+        let input = TABLE.replace("0x2000'0000    0x50", "0x2000'0000    fifty");
+        match parse_init_table(input.as_bytes()) {
+            Err(Error::MalformedRow { section: "INIT TABLE", line: 15, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+}
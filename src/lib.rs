@@ -1,5 +1,10 @@
 //! # iarmap
 //!
+//! `parse_map` parses every section of an IAR map file into a `MapFile`. The
+//! sections may also be parsed one at a time, as described below, and
+//! `ParseOptions` parses a chosen set of sections, optionally recording
+//! malformed rows as warnings rather than failing.
+//!
 //! The library defines a parser for IAR map files. The library can parse the
 //! module summary table of an IAR map files, returning the table(s) as a
 //! collection of key-values. For instance, a module summary table with the
@@ -21,57 +26,181 @@
 //!     Total:                              30      50       77
 //! ```
 //!
-//! is parsed into a vector of `ObjModuleTable`s. A `Module` contains the
-//! three data sizes. `Modules` may be found in an `ObjModuleTable`'s `table`
-//! member. The keys are the object file names. The brief example below
-//! demonstrates the representation of "Bar.o" from the table above.
+//! is parsed into a vector of `ObjModuleTable`s. Each `ObjModuleTable` also
+//! keeps the archive's `Total:` row, and `parse_module_summary` returns a
+//! `ModuleSummary` that adds the image-wide `Gaps`, `Linker created`, and
+//! `Grand Total:` rows. `ModuleSummary::validate` cross-checks the parsed rows
+//! against those linker-computed totals. Each archive's `[N]` index is parsed
+//! into `ObjModuleTable::index`, and `ModuleSummary::archive` finds the archive
+//! that a `Symbol` or `PlacedSection` refers to.
+//!
+//! `diff` compares the module summaries of two map files, archive by archive,
+//! returning a `MapDiff` with the archives and objects only in one of them,
+//! and the change in size of each archive and object in both.
+//!
+//! `parse_map_header` parses the banner at the top of the map file into a
+//! `MapHeader`, which names the linker, its version, and the files it used.
+//! `parse_entry_list` parses the `ENTRY LIST` section into `Symbol`s, and
+//! `parse_placement_summary` parses the `PLACEMENT SUMMARY` section into the
+//! sections placed in each memory region, and `parse_memory_regions` reports
+//! the used and free space of those regions. `parse_init_entries` parses the
+//! `INIT TABLE` section into an `InitTable`, which tells how much RAM is
+//! zero-initialized and how much is copied from flash. `parse_stack_usage`
+//! parses the `STACK USAGE` section into the worst-case stack depth and call
+//! chain of each call graph root. `parse_runtime_model` parses the `RUNTIME
+//! MODEL ATTRIBUTES` section into the library and ABI settings of each object.
+//!
+//! A `Module` contains the data sizes of an object. An `ObjModuleTable`'s
+//! `table` member has a `ModuleRow` for each object, in the order of the map
+//! file, with the object file name and the line that lists it. The brief
+//! example below demonstrates the representation of "Bar.o" from the table
+//! above.
 //!
 //! ```
-//! use iarmap::Module;
-//! use std::collections::HashMap;
+//! use iarmap::{Module, ModuleRow};
 //!
-//! let bar = Module{ ro_code: Some(33), ro_data: Some(44), rw_data: None };
-//! let mut table: HashMap<String, Module> = HashMap::new();
-//! table.insert("Bar.o".into(), bar);
+//! let bar = Module{ ro_code: Some(22), ro_data: Some(44), rw_data: None, ..Default::default() };
+//! let row = ModuleRow{ name: "Bar.o".into(), module: bar, line: 4 };
 //! ```
 
-#[macro_use]
+// nom's named! macros discard the doc comments we write on our parsers
+#![allow(unused_doc_comments)]
+
 extern crate nom;
 
-use nom::IResult;
+mod error;
+pub use error::Error;
+
+mod section;
+
+mod header;
+use header::parse_banner;
+pub use header::MapHeader;
+
+mod entry;
+use entry::parse_entries;
+pub use entry::{Binding, Symbol, SymbolKind};
+
+mod diff;
+pub use diff::{diff, ArchiveDiff, ArchiveEntry, Change, DiffOptions, MapDiff, Measure, ObjectChange, ObjectEntry, ObjectMove};
+
+mod init;
+use init::parse_init_table;
+pub use init::{InitEntry, InitKind, InitRange, InitTable};
+
+mod map;
+pub use map::MapFile;
+
+mod options;
+pub use options::{ParseOptions, Section};
+
+mod placement;
+use placement::parse_placements;
+pub use placement::{AddressRange, MemoryRegion, PlacedSection, PlacementBlock, PlacementDirective,
+                    PlacementSummary, SectionKind};
+
+mod runtime;
+use runtime::parse_runtime_attributes;
+pub use runtime::{Attributes, IndexedObject, RuntimeAttributes};
+
+mod stack;
+use stack::parse_stack;
+pub use stack::{StackCategory, StackFrame, StackRoot, StackUsage};
 
 mod summary;
 use summary::parse_module_summaries;
-pub use summary::{Module, ObjModuleTable};
+pub use summary::{Duplicate, Module, ModuleDelta, ModuleRow, ModuleSummary, ObjModuleTable, ObjectKey, TotalMismatch, TotalRow};
 
 use std::io::Read;
 
-/// Parse a map file, returning the module summary table, or a string
-/// representing an error message.
-///
-/// The function takes ownership of the reader to locate and consume the module
-/// summary table.
-pub fn parse_map_file<R: Read>(mut reader: R) -> Result<Vec<ObjModuleTable>, &'static str> {
+/// Parse every section of a map file, returning a `MapFile` with `None` for the
+/// sections that the map file does not have, or an `Error` describing the first
+/// section that could not be parsed. `ParseOptions` selects the sections, and
+/// may parse them leniently.
+pub fn parse_map<R: Read>(reader: R) -> Result<MapFile, Error> {
+    ParseOptions::new().parse(reader)
+}
+
+/// Parse the banner at the top of a map file, returning the linker and the
+/// files that it used, or an `Error`.
+pub fn parse_map_header<R: Read>(mut reader: R) -> Result<MapHeader, Error> {
     let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_banner(&buffer)
+}
 
-    if let Err(_) = reader.read_to_end(&mut buffer) {
-        return Err("Failed to read");
-    }
+/// Parse the entry list of a map file, returning its symbols in the order the
+/// linker listed them, or an `Error`.
+pub fn parse_entry_list<R: Read>(mut reader: R) -> Result<Vec<Symbol>, Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_entries(&buffer)
+}
+
+/// Parse the placement summary of a map file, returning the placement
+/// directives and the sections that each placed, or an `Error`.
+pub fn parse_placement_summary<R: Read>(mut reader: R) -> Result<PlacementSummary, Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_placements(&buffer)
+}
+
+/// Parse the memory regions of a map file, returning the used and free space of
+/// each region that the placement directives place sections in, or an `Error`.
+pub fn parse_memory_regions<R: Read>(reader: R) -> Result<Vec<MemoryRegion>, Error> {
+    parse_placement_summary(reader).map(|summary| summary.regions())
+}
+
+/// Parse the init table of a map file, returning the entries that initialize
+/// RAM at startup, or an `Error`.
+pub fn parse_init_entries<R: Read>(mut reader: R) -> Result<InitTable, Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_init_table(&buffer)
+}
+
+/// Parse the stack usage of a map file linked with `--stack_usage`, returning
+/// the call graph roots and their worst-case stack depths, or an `Error`.
+pub fn parse_stack_usage<R: Read>(mut reader: R) -> Result<StackUsage, Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_stack(&buffer)
+}
 
-    match parse_module_summaries(&buffer) {
-        IResult::Done(_, objsummaries) => Ok(objsummaries),
-        _ => {
-            return Err("Failed to parse");
-        }
+/// Parse the runtime model attributes of a map file, returning the attributes
+/// of the image and of each object, or an `Error`.
+pub fn parse_runtime_model<R: Read>(mut reader: R) -> Result<RuntimeAttributes, Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_runtime_attributes(&buffer)
+}
+
+/// Parse a map file, returning the module summary table, or an `Error`
+/// describing why the map file could not be parsed.
+///
+/// The function takes ownership of the reader to locate and consume the module
+/// summary table. It is kept for compatibility; `parse_map` parses the module
+/// summary along with the other sections.
+pub fn parse_map_file<R: Read>(reader: R) -> Result<Vec<ObjModuleTable>, Error> {
+    match ParseOptions::new().sections(&[Section::ModuleSummary]).parse(reader)?.module_summary {
+        Some(summary) => Ok(summary.tables),
+        None => Err(Error::MissingSection("MODULE SUMMARY")),
     }
 }
 
+/// Parse a map file, returning the complete module summary, including the
+/// linker's `Gaps`, `Linker created`, and `Grand Total:` rows, or an `Error`.
+pub fn parse_module_summary<R: Read>(mut reader: R) -> Result<ModuleSummary, Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_module_summaries(&buffer)
+}
+
 #[cfg(test)]
 mod tests {
 
-    use std::collections::HashMap;
-    use summary::{Module, ObjModuleTable};
-    use super::parse_map_file;
+    use summary::{Module, ModuleRow, ModuleSummary, ObjModuleTable};
+    use super::{parse_entry_list, parse_map, parse_map_file, parse_module_summary};
 
     #[test]
     fn test_parse_map_file() {
@@ -145,71 +274,146 @@ Entry                      Address    Size  Type      Object
 ?main                   0x080e499d          Code  Gb  cmain.o [7]"
             .as_bytes();
 
-        let mut table1: HashMap<String, Module> = HashMap::new();
-        table1.insert(
-            "The_Alarm_Logging.o".into(),
-            Module {
-                ro_code: Some(88),
-                ro_data: Some(152),
-                rw_data: Some(72),
-            },
-        );
-        table1.insert(
-            "Alarm_Log.o".into(),
-            Module {
-                ro_code: Some(390),
-                ro_data: None,
-                rw_data: None,
-            },
-        );
-        table1.insert(
-            "More_Alarm_Log.o".into(),
-            Module {
-                ro_code: Some(168),
-                ro_data: None,
-                rw_data: Some(20),
-            },
-        );
+        let table1: Vec<(&str, Module)> = vec![
+            (
+                "The_Alarm_Logging.o",
+                Module {
+                    ro_code: Some(88),
+                    ro_data: Some(152),
+                    rw_data: Some(72),
+                    ..Default::default()
+                },
+            ),
+            (
+                "Alarm_Log.o",
+                Module {
+                    ro_code: Some(390),
+                    ro_data: None,
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+            (
+                "More_Alarm_Log.o",
+                Module {
+                    ro_code: Some(168),
+                    ro_data: None,
+                    rw_data: Some(20),
+                    ..Default::default()
+                },
+            ),
+        ];
 
-        let table2: HashMap<String, Module> = HashMap::new();
+        let table2: Vec<(&str, Module)> = Vec::new();
 
-        let mut table3: HashMap<String, Module> = HashMap::new();
-        table3.insert(
-            "FAT_CheckDisk.o".into(),
-            Module {
-                ro_code: Some(2_924),
-                ro_data: Some(48),
-                rw_data: Some(16),
-            },
-        );
-        table3.insert(
-            "FAT_Dir.o".into(),
-            Module {
-                ro_code: Some(536),
-                ro_data: Some(24),
-                rw_data: None,
-            },
-        );
+        let table3: Vec<(&str, Module)> = vec![
+            (
+                "FAT_CheckDisk.o",
+                Module {
+                    ro_code: Some(2_924),
+                    ro_data: Some(48),
+                    rw_data: Some(16),
+                    ..Default::default()
+                },
+            ),
+            (
+                "FAT_Dir.o",
+                Module {
+                    ro_code: Some(536),
+                    ro_data: Some(24),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+        ];
 
         let m1 = ObjModuleTable {
             name: "C:\\!prj\\Foo\\Bar\\Baz\\Obj: [1]".into(),
-            table: table1,
+            path: "C:\\!prj\\Foo\\Bar\\Baz\\Obj".into(),
+            index: Some(1),
+            line: 37,
+            table: ModuleRow::consecutive(table1, 38),
+            total: Module {
+                ro_code: Some(385_176),
+                ro_data: Some(616_821),
+                rw_data: Some(210_147),
+                ..Default::default()
+            },
         };
         let m2 = ObjModuleTable {
             name: "command line: [2]".into(),
-            table: table2,
+            path: "command line".into(),
+            index: Some(2),
+            line: 44,
+            table: ModuleRow::consecutive(table2, 45),
+            total: Module {
+                ro_code: None,
+                ro_data: None,
+                rw_data: None,
+                ..Default::default()
+            },
         };
         let m3 = ObjModuleTable {
             name: "FileSys.a: [3]".into(),
-            table: table3,
+            path: "FileSys.a".into(),
+            index: Some(3),
+            line: 48,
+            table: ModuleRow::consecutive(table3, 49),
+            total: Module {
+                ro_code: Some(68),
+                ro_data: None,
+                rw_data: None,
+                ..Default::default()
+            },
         };
 
         let expected = vec![m1, m2, m3];
 
         let result = parse_map_file(input);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
 
         let actual = result.unwrap();
         assert_eq!(actual, expected);
+
+        let summary = parse_module_summary(input).unwrap();
+        assert_eq!(summary.tables, expected);
+        assert_eq!(summary.archive(3).map(|a| a.path.as_str()), Some("FileSys.a"));
+        assert!(summary.archive(7).is_none());
+        assert_eq!(
+            summary,
+            ModuleSummary {
+                tables: expected,
+                gaps: Some(Module {
+                    ro_code: Some(96),
+                    ro_data: Some(90),
+                    rw_data: Some(9),
+                    ..Default::default()
+                }),
+                linker_created: Some(Module {
+                    ro_code: None,
+                    ro_data: Some(88),
+                    rw_data: Some(378_432),
+                    ..Default::default()
+                }),
+                grand_total: Some(Module {
+                    ro_code: Some(492_776),
+                    ro_data: Some(630_240),
+                    rw_data: Some(591_176),
+                    ..Default::default()
+                }),
+            }
+        );
+
+        let map = parse_map(input).unwrap();
+        assert_eq!(map.module_summary, Some(summary));
+        assert_eq!(map.header.map(|h| h.linker), Some("THE TYPICAL IAR HEADER".into()));
+        assert_eq!(map.entries.map(|e| e.len()), Some(3));
+        assert_eq!(map.placement, None);
+
+        let symbols = parse_entry_list(input).unwrap();
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols[2].name, "?main");
+        assert_eq!(symbols[2].object, "cmain.o");
+        assert_eq!(symbols[2].archive, Some(7));
     }
 }
@@ -0,0 +1,164 @@
+//! The map file library module, which parses every section of a map file
+
+use entry::{parse_entries_with, Symbol};
+use error::Error;
+use header::{parse_banner, MapHeader};
+use init::{parse_init_table_with, InitTable};
+use options::{ParseOptions, Section};
+use placement::{parse_placements_with, PlacementSummary};
+use runtime::{parse_runtime_attributes_with, RuntimeAttributes};
+use section::Warnings;
+use stack::{parse_stack_with, StackUsage};
+use summary::{parse_module_summaries_with, ModuleSummary};
+
+/// Every section of a map file.
+///
+/// A section is `None` when the map file does not have it, or when it was not
+/// selected by the `ParseOptions`. The linker only writes some sections for
+/// some options, such as the `STACK USAGE` for `--stack_usage`. A lenient
+/// parse records the rows and sections that it skipped in `warnings`.
+#[derive(Debug, Default)]
+pub struct MapFile {
+    pub header: Option<MapHeader>,
+    pub runtime_attributes: Option<RuntimeAttributes>,
+    pub placement: Option<PlacementSummary>,
+    pub init_table: Option<InitTable>,
+    pub module_summary: Option<ModuleSummary>,
+    pub entries: Option<Vec<Symbol>>,
+    pub stack_usage: Option<StackUsage>,
+    pub warnings: Vec<Error>,
+}
+
+/// Parses the sections of one map file, collecting the warnings of a lenient
+/// parse
+struct Sections<'a> {
+    input: &'a [u8],
+    options: &'a ParseOptions,
+    warnings: Vec<Error>,
+}
+
+impl<'a> Sections<'a> {
+    /// Parses the section if it is selected, turning a missing section into
+    /// `None`. A lenient parse skips each malformed row as the section's parser
+    /// reaches it, and skips a section that still cannot be parsed.
+    fn parse<T>(&mut self, section: Section, parser: fn(&[u8], &mut Warnings) -> Result<T, Error>) -> Result<Option<T>, Error> {
+        if !self.options.selects(section) {
+            return Ok(None);
+        }
+
+        let lenient = self.options.is_lenient();
+        let result = if lenient {
+            parser(self.input, &mut Warnings::lenient(&mut self.warnings))
+        } else {
+            parser(self.input, &mut Warnings::strict())
+        };
+        match result {
+            Ok(section) => Ok(Some(section)),
+            Err(Error::MissingHeader) | Err(Error::MissingSection(_)) => Ok(None),
+            Err(err) if lenient => {
+                self.warnings.push(err);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Parses the sections of an IAR map file that the options select
+pub fn parse_sections(input: &[u8], options: &ParseOptions) -> Result<MapFile, Error> {
+    let mut sections = Sections { input, options, warnings: Vec::new() };
+    Ok(MapFile {
+        header: sections.parse(Section::Header, |input, _| parse_banner(input))?,
+        runtime_attributes: sections.parse(Section::RuntimeAttributes, parse_runtime_attributes_with)?,
+        placement: sections.parse(Section::Placement, parse_placements_with)?,
+        init_table: sections.parse(Section::InitTable, parse_init_table_with)?,
+        module_summary: sections.parse(Section::ModuleSummary, parse_module_summaries_with)?,
+        entries: sections.parse(Section::EntryList, parse_entries_with)?,
+        stack_usage: sections.parse(Section::StackUsage, parse_stack_with)?,
+        warnings: sections.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    static MAP: &str = "*******************************************************************************
+*** INIT TABLE
+***
+
+          Address      Size
+          -------      ----
+Zero (__iar_zero_init3)
+    1 destination range, total size 0x1c4c:
+          0x2000'0008  lots
+
+*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                           ro code  ro data  rw data
+    ------                           -------  -------  -------
+libA.a: [1]
+    a.o                                   88      152       72
+    b.o                                  3?0
+    c.o                                  168                20
+    ----------------------------------------------------------
+    Total:                               256      152       92
+
+libB.a: [2]
+    d.o                                  nope
+    ----------------------------------------------------------
+    Total:
+
+    Grand Total:                         256      152       92
+";
+
+    #[test]
+    fn test_parse_sections_missing() {
+        let map = parse_sections(b"nothing to see here\n", &ParseOptions::new()).unwrap();
+        assert!(map.header.is_none());
+        assert!(map.module_summary.is_none());
+        assert!(map.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_sections_malformed() {
+        match parse_sections(MAP.as_bytes(), &ParseOptions::new()) {
+            Err(Error::MalformedRow { section: "INIT TABLE", line: 9, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_sections_selected() {
+        let options = ParseOptions::new().sections(&[Section::ModuleSummary]);
+        match parse_sections(MAP.as_bytes(), &options) {
+            Err(Error::MalformedRow { section: "MODULE SUMMARY", line: 19, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_sections_lenient() {
+        let map = parse_sections(MAP.as_bytes(), &ParseOptions::new().lenient(true)).unwrap();
+
+        let lines: Vec<(&str, usize)> = map
+            .warnings
+            .iter()
+            .map(|warning| match *warning {
+                Error::MalformedRow { section, line, .. } => (section, line),
+                _ => panic!("{:?}", warning),
+            })
+            .collect();
+        assert_eq!(lines, vec![("INIT TABLE", 9), ("MODULE SUMMARY", 19), ("MODULE SUMMARY", 25)]);
+
+        assert_eq!(map.init_table.unwrap().entries[0].destination, vec![]);
+        let summary = map.module_summary.unwrap();
+        assert_eq!(summary.tables.len(), 2);
+        assert_eq!(summary.tables[0].table.len(), 2);
+        assert!(summary.tables[1].table.is_empty());
+        assert!(summary.grand_total.is_some());
+    }
+}
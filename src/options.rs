@@ -0,0 +1,128 @@
+//! The parse options library module
+
+use error::Error;
+use map::{parse_sections, MapFile};
+
+use std::io::Read;
+
+/// A section of a map file
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Section {
+    Header,
+    RuntimeAttributes,
+    Placement,
+    InitTable,
+    ModuleSummary,
+    EntryList,
+    StackUsage,
+}
+
+impl Section {
+    /// Every section, in the order they appear in a map file
+    pub const ALL: [Section; 7] = [
+        Section::Header,
+        Section::RuntimeAttributes,
+        Section::Placement,
+        Section::InitTable,
+        Section::ModuleSummary,
+        Section::EntryList,
+        Section::StackUsage,
+    ];
+}
+
+/// Chooses the sections to parse, and how to handle the sections that cannot
+/// be parsed.
+///
+/// By default, every section is parsed, and the first malformed section is an
+/// `Error`. A lenient parse instead drops each malformed row, records it in
+/// `MapFile::warnings`, and keeps the rest of the section. A section whose
+/// column header cannot be parsed is skipped, and also recorded.
+///
+/// ```no_run
+/// use iarmap::{ParseOptions, Section};
+/// use std::fs::File;
+///
+/// let map = ParseOptions::new()
+///     .sections(&[Section::Header, Section::ModuleSummary])
+///     .lenient(true)
+///     .parse(File::open("app.map").unwrap())
+///     .unwrap();
+/// for warning in &map.warnings {
+///     println!("skipped {}", warning);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    sections: Vec<Section>,
+    lenient: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            sections: Section::ALL.to_vec(),
+            lenient: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options that strictly parse every section
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Parse only the sections
+    pub fn sections(mut self, sections: &[Section]) -> ParseOptions {
+        self.sections = sections.to_vec();
+        self
+    }
+
+    /// Do not parse the section
+    pub fn skip(mut self, section: Section) -> ParseOptions {
+        self.sections.retain(|s| *s != section);
+        self
+    }
+
+    /// Record malformed rows and sections as warnings, rather than errors
+    pub fn lenient(mut self, lenient: bool) -> ParseOptions {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Returns true if the section is parsed
+    pub(crate) fn selects(&self, section: Section) -> bool {
+        self.sections.contains(&section)
+    }
+
+    /// Returns true if malformed rows and sections are warnings
+    pub(crate) fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Parse the map file with these options
+    pub fn parse<R: Read>(&self, mut reader: R) -> Result<MapFile, Error> {
+        let mut buffer: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        parse_sections(&buffer, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let options = ParseOptions::new().skip(Section::EntryList).lenient(true);
+        assert!(!options.selects(Section::EntryList));
+        assert!(options.selects(Section::ModuleSummary));
+        assert!(options.is_lenient());
+
+        let options = ParseOptions::new().sections(&[Section::InitTable]);
+        assert!(options.selects(Section::InitTable));
+        assert!(!options.selects(Section::Header));
+        assert!(!options.is_lenient());
+    }
+}
@@ -0,0 +1,625 @@
+//! The "placement summary" library module
+
+use nom::*;
+
+use error::Error;
+use section::{column_header, linep, parse_hex, section_start, skip_blank_lines, quoted_name, split_archive_index, words, Warnings};
+
+use std::str;
+
+/// The title of the placement summary section
+const SECTION: &str = "PLACEMENT SUMMARY";
+
+/// An inclusive range of addresses, as in `[from 0x800'0000 to 0x80f'ffff]`
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct AddressRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl AddressRange {
+    /// Returns the range from start to end, or None if end is below start
+    pub fn new(start: u64, end: u64) -> Option<AddressRange> {
+        if start <= end { Some(AddressRange { start, end }) } else { None }
+    }
+
+    /// Returns true if the address is within the range
+    pub fn contains(&self, address: u64) -> bool {
+        self.start <= address && address <= self.end
+    }
+
+    /// Returns the number of bytes in the range. A reversed range has no
+    /// bytes, and the whole address space saturates at `u64::MAX`.
+    pub fn size(&self) -> u64 {
+        if self.start <= self.end { (self.end - self.start).saturating_add(1) } else { 0 }
+    }
+
+    /// Returns the number of bytes in both ranges
+    pub fn overlap(&self, other: &AddressRange) -> u64 {
+        let (start, end) = (self.start.max(other.start), self.end.min(other.end));
+        AddressRange { start, end }.size()
+    }
+}
+
+/// The kind of a placed section, as shown in the `Kind` column
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SectionKind {
+    RoCode,
+    RwCode,
+    RoData,
+    RwData,
+    Const,
+    Inited,
+    Uninit,
+    Zero,
+}
+
+impl SectionKind {
+    /// Returns the kind with the text used in the `Kind` column
+    fn from_text(text: &str) -> Option<SectionKind> {
+        match text {
+            "ro code" => Some(SectionKind::RoCode),
+            "rw code" => Some(SectionKind::RwCode),
+            "ro data" => Some(SectionKind::RoData),
+            "rw data" => Some(SectionKind::RwData),
+            "const" => Some(SectionKind::Const),
+            "inited" => Some(SectionKind::Inited),
+            "uninit" => Some(SectionKind::Uninit),
+            "zero" => Some(SectionKind::Zero),
+            _ => None,
+        }
+    }
+}
+
+/// A named placement directive from the linker configuration, such as
+///
+/// ```text
+/// "P1":  place in [from 0x800'0000 to 0x80f'ffff] { ro };
+/// "A0":  place at address 0x800'0000 { ro section .intvec };
+/// ```
+///
+/// `text` is the complete directive, with wrapped lines joined.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlacementDirective {
+    pub name: String,
+    pub place_at: Option<u64>,
+    pub place_in: Vec<AddressRange>,
+    pub text: String,
+}
+
+/// A row of a placement block.
+///
+/// For a row
+///
+/// ```text
+///   .text              ro code   0x800'01d8   0xe1c  main.o [1]
+/// ```
+///
+/// the `section` is `.text`, the `kind` is `Some(SectionKind::RoCode)`, the
+/// `object` is `main.o`, and the `archive` is `Some(1)`. Rows for linker
+/// blocks, such as `CSTACK`, have no kind, and an `object` like `<Block>`.
+/// Rows nested within a block have a `level` greater than zero.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlacedSection {
+    pub section: String,
+    pub kind: Option<SectionKind>,
+    pub address: u64,
+    pub size: u64,
+    pub object: String,
+    pub archive: Option<usize>,
+    pub level: usize,
+}
+
+/// The sections placed by one placement directive.
+///
+/// For a block
+///
+/// ```text
+/// "P2", part 1 of 2:                            0x8
+///   P2-1                        0x2000'0000     0x8  <Init block>
+///     .data            inited   0x2000'0000     0x4  main.o [1]
+///                             - 0x2000'0008     0x8
+/// ```
+///
+/// the `name` is `P2`, the `part` is `Some((1, 2))`, and the `size` is `0x8`.
+/// `place_at` and `place_in` are copied from the directive with the same name.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlacementBlock {
+    pub name: String,
+    pub part: Option<(usize, usize)>,
+    pub size: Option<u64>,
+    pub place_at: Option<u64>,
+    pub place_in: Vec<AddressRange>,
+    pub sections: Vec<PlacedSection>,
+}
+
+/// A memory region that a directive places sections in, such as the
+/// `[from 0x800'0000 to 0x80f'ffff]` of `"P1"`. `start` and `end` are
+/// inclusive. `free` is the size of the unused ranges within the region, and
+/// `used` is the rest of the region.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub start: u64,
+    pub end: u64,
+    pub used: u64,
+    pub free: u64,
+}
+
+/// The placement summary of a map file. `unused` has the ranges of the
+/// `Unused ranges` table that follows the placement blocks.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PlacementSummary {
+    pub directives: Vec<PlacementDirective>,
+    pub blocks: Vec<PlacementBlock>,
+    pub unused: Vec<AddressRange>,
+}
+
+impl PlacementSummary {
+    /// Returns a region for each `place in` range of each directive. Directives
+    /// that `place at` an address have no region. Directives that place in the
+    /// same range each report the range's free space. Unused ranges that
+    /// overlap are only counted once, so `free` is at most the region's size.
+    pub fn regions(&self) -> Vec<MemoryRegion> {
+        let unused = merged(&self.unused);
+        let mut regions = Vec::new();
+        for directive in &self.directives {
+            for range in &directive.place_in {
+                let free = unused
+                    .iter()
+                    .fold(0u64, |free, unused| free.saturating_add(range.overlap(unused)))
+                    .min(range.size());
+                regions.push(MemoryRegion {
+                    name: directive.name.clone(),
+                    start: range.start,
+                    end: range.end,
+                    used: range.size().saturating_sub(free),
+                    free,
+                });
+            }
+        }
+        regions
+    }
+}
+
+/// Returns the ranges sorted by start, with overlapping and adjacent ranges
+/// merged into one
+fn merged(ranges: &[AddressRange]) -> Vec<AddressRange> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_by_key(|range| (range.start, range.end));
+    let mut merged: Vec<AddressRange> = Vec::new();
+    for range in sorted {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Parses every `[from X to Y]` range in the text, skipping reversed ranges
+fn rangesp(text: &str) -> Vec<AddressRange> {
+    let words = words(text);
+    words
+        .windows(4)
+        .filter_map(|w| match (w[0].1, w[2].1) {
+            ("[from", "to") => AddressRange::new(parse_hex(w[1].1)?, parse_hex(w[3].1.trim_end_matches(']'))?),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses a placement directive statement
+fn directivep(statement: &str) -> Option<PlacementDirective> {
+    let (name, rest) = quoted_name(statement)?;
+    let words = words(rest);
+    let place_at = words
+        .windows(4)
+        .find(|w| w[0].1 == "place" && w[1].1 == "at" && w[2].1 == "address")
+        .and_then(|w| parse_hex(w[3].1));
+    Some(PlacementDirective {
+        name: name.into(),
+        place_at,
+        place_in: rangesp(rest),
+        text: statement.into(),
+    })
+}
+
+/// Parses a block header, such as `"P2", part 1 of 2:    0x8`, into an empty
+/// block with the name, the part, and the size
+fn block_headerp(row: &str) -> Option<PlacementBlock> {
+    let (name, rest) = quoted_name(row)?;
+    let colon = rest.find(':')?;
+
+    let mut part = rest[..colon].trim_start_matches(',').split_whitespace();
+    let part = match (part.next(), part.next(), part.next(), part.next()) {
+        (None, ..) => None,
+        (Some("part"), Some(n), Some("of"), Some(m)) => Some((n.parse().ok()?, m.parse().ok()?)),
+        _ => return None,
+    };
+
+    let mut size = rest[colon + 1..].split_whitespace();
+    let size = match (size.next(), size.next()) {
+        (None, _) => None,
+        (Some(size), None) => Some(parse_hex(size)?),
+        _ => return None,
+    };
+
+    Some(PlacementBlock {
+        name: name.into(),
+        part,
+        size,
+        place_at: None,
+        place_in: Vec::new(),
+        sections: Vec::new(),
+    })
+}
+
+/// Parses a row of a placement block. The columns are found by their content:
+/// the address and size are the first two hexadecimal words, the kind is the
+/// known kind before the address, and the object is everything after the
+/// size.
+fn placedp(row: &str, indent: usize) -> Option<PlacedSection> {
+    let words = words(row);
+    let at = words.iter().skip(1).position(|&(_, w)| parse_hex(w).is_some())? + 1;
+    let address = parse_hex(words[at].1)?;
+    let size = parse_hex(words.get(at + 1)?.1)?;
+    let object = words.get(at + 2).map_or("", |&(offset, _)| row[offset..].trim_end());
+    let (object, archive) = split_archive_index(object);
+
+    // The kind is one or two words, and the section name needs at least one
+    let text = |from: usize, to: usize| &row[words[from].0..words[to].0 + words[to].1.len()];
+    let two = if at >= 3 { SectionKind::from_text(text(at - 2, at - 1)) } else { None };
+    let one = if at >= 2 { SectionKind::from_text(words[at - 1].1) } else { None };
+    let (name_end, kind) = match (two, one) {
+        (Some(kind), _) => (at - 2, Some(kind)),
+        (None, Some(kind)) => (at - 1, Some(kind)),
+        (None, None) => (at, None),
+    };
+    let first = words[0].0;
+
+    Some(PlacedSection {
+        section: text(0, name_end - 1).into(),
+        kind,
+        address,
+        size,
+        object: object.into(),
+        archive,
+        level: first.saturating_sub(indent) / 2,
+    })
+}
+
+/// Parses a `0x800'1028   0x80f'ffff  0xf'efd8` row of the unused ranges
+fn unusedp(row: &str) -> Option<AddressRange> {
+    match words(row)[..] {
+        [(_, start), (_, end), (_, size)] => {
+            parse_hex(size)?;
+            AddressRange::new(parse_hex(start)?, parse_hex(end)?)
+        }
+        _ => None,
+    }
+}
+
+/// Parses the `Unused ranges` table, which ends at a blank line
+fn parse_unused(input: &[u8], rest: &[u8], warnings: &mut Warnings) -> Result<Vec<AddressRange>, Error> {
+    let mut rest = match linep(rest) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+    };
+    rest = match column_header(rest, "From") {
+        IResult::Done(rest, _) => rest,
+        _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+    };
+
+    let mut unused = Vec::new();
+    while let IResult::Done(after, line) = linep(rest) {
+        let text = str::from_utf8(line);
+        if text.is_ok_and(|text| text.trim().is_empty() || text.starts_with('*')) {
+            break;
+        }
+        match text.ok().and_then(unusedp) {
+            Some(range) => unused.push(range),
+            None => warnings.malformed_row(SECTION, input, rest)?,
+        }
+        rest = after;
+    }
+    Ok(unused)
+}
+
+/// Returns true if the line is the column header of the block rows
+fn is_column_header(line: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(line);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.first() == Some(&"Section") && words.contains(&"Address")
+}
+
+/// Parses the placement summary from an IAR map file
+pub fn parse_placements(input: &[u8]) -> Result<PlacementSummary, Error> {
+    parse_placements_with(input, &mut Warnings::strict())
+}
+
+/// Parses the placement summary, reporting each malformed row to the warnings
+pub fn parse_placements_with(input: &[u8], warnings: &mut Warnings) -> Result<PlacementSummary, Error> {
+    let mut rest = match section_start(input, SECTION) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::MissingSection(SECTION)),
+    };
+
+    // The directives come before the column header, and a directive may
+    // continue over several lines until its ';'
+    let mut summary = PlacementSummary::default();
+    let mut statement = String::new();
+    loop {
+        let (after, line) = match linep(rest) {
+            IResult::Done(after, line) if !line.is_empty() || !after.is_empty() => (after, line),
+            _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+        };
+        if is_column_header(line) {
+            break;
+        }
+        if line.starts_with(b"*") {
+            return Err(Error::malformed_column_header(SECTION, input, rest));
+        }
+        let text = match str::from_utf8(line) {
+            Ok(text) => text,
+            Err(_) => {
+                warnings.malformed_row(SECTION, input, rest)?;
+                rest = after;
+                continue;
+            }
+        };
+        if !text.trim().is_empty() {
+            if !statement.is_empty() {
+                statement.push(' ');
+            }
+            statement.push_str(text.trim());
+            if statement.ends_with(';') {
+                summary.directives.extend(directivep(&statement));
+                statement.clear();
+            }
+        }
+        rest = after;
+    }
+
+    let indent = rest.iter().take_while(|c| **c == b' ').count();
+    rest = match column_header(rest, "Section") {
+        IResult::Done(rest, _) => rest,
+        _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+    };
+
+    while let IResult::Done(after, line) = linep(rest) {
+        if rest.is_empty() || line.starts_with(b"*") || line.starts_with(b"Unused ranges") {
+            break;
+        }
+        let (row, text) = (rest, str::from_utf8(line));
+        rest = after;
+        let text = match text {
+            Ok(text) => text,
+            Err(_) => {
+                warnings.malformed_row(SECTION, input, row)?;
+                continue;
+            }
+        };
+        let trimmed = text.trim();
+
+        if trimmed.is_empty() || (trimmed.starts_with("- ") && words(trimmed).len() == 3) {
+            // Blank lines and the "- 0x800'427c  0x40a4" lines that end blocks
+        } else if let Some(mut block) = block_headerp(trimmed) {
+            if let Some(directive) = summary.directives.iter().find(|d| d.name == block.name) {
+                block.place_at = directive.place_at;
+                block.place_in = directive.place_in.clone();
+            }
+            summary.blocks.push(block);
+        } else {
+            match (placedp(text, indent), summary.blocks.last_mut()) {
+                (Some(placed), Some(block)) => block.sections.push(placed),
+                _ => warnings.malformed_row(SECTION, input, row)?,
+            }
+        }
+    }
+
+    if rest.starts_with(b"Unused ranges") {
+        summary.unused = parse_unused(input, rest, warnings)?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    static SUMMARY: &str = "*******************************************************************************
+*** PLACEMENT SUMMARY
+***
+
+\"A0\":  place at address 0x800'0000 { ro section .intvec };
+\"P1\":  place in [from 0x800'0000 to 0x80f'ffff] { ro };
+define block CSTACK with size = 8K, alignment = 8 { };
+\"P2\":  place in [from 0x2000'0000 to 0x2001'ffff] {
+          rw, block CSTACK };
+initialize by copy { rw };
+
+  Section            Kind         Address    Size  Object
+  -------            ----         -------    ----  ------
+\"A0\":                                       0x1d8
+  .intvec            ro code   0x800'0000   0x1d8  startup_stm32f407xx.o [1]
+                             - 0x800'01d8   0x1d8
+
+\"P1\":                                      0xe48
+  .text              ro code   0x800'01d8   0xe1c  main.o [1]
+  .text              ro code   0x800'0ff4    0x2c  I64DivMod.o [4]
+  Initializer bytes  const     0x800'1020     0x8  <for P2-1>
+                             - 0x800'1028   0xe50
+
+\"P2\", part 1 of 2:                            0x8
+  P2-1                        0x2000'0000     0x8  <Init block>
+    .data            inited   0x2000'0000     0x4  main.o [1]
+                            - 0x2000'0008     0x8
+
+\"P2\", part 2 of 2:                         0x2000
+  CSTACK                      0x2000'0008  0x2000  <Block>
+    CSTACK           uninit   0x2000'0008  0x2000  <Block tail>
+                            - 0x2000'2008  0x2000
+
+Unused ranges:
+
+         From           To      Size
+         ----           --      ----
+   0x800'1028   0x80f'ffff  0xf'efd8
+";
+
+    #[test]
+    fn test_rangesp() {
+        assert_eq!(
+            rangesp("place in [from 0x0 to 0xff] | [from 0x200 to 0x2ff] { ro };"),
+            vec![AddressRange{ start: 0x0, end: 0xff }, AddressRange{ start: 0x200, end: 0x2ff }]
+        );
+        assert_eq!(rangesp("place in [from 0x2ff to 0x200] { ro };"), vec![]);
+    }
+
+    #[test]
+    fn test_address_range() {
+        assert_eq!(AddressRange::new(0x200, 0x1ff), None);
+        assert_eq!(AddressRange::new(0x200, 0x2ff).map(|range| range.size()), Some(0x100));
+        assert_eq!(AddressRange{ start: 0x200, end: 0x1ff }.size(), 0);
+        assert_eq!(AddressRange{ start: 0, end: u64::MAX }.size(), u64::MAX);
+        assert_eq!(AddressRange{ start: 0, end: u64::MAX }.overlap(&AddressRange{ start: 0x100, end: 0x1ff }), 0x100);
+    }
+
+    #[test]
+    fn test_unusedp() {
+        assert_eq!(unusedp("   0x800'1028   0x80f'ffff  0xf'efd8"), AddressRange::new(0x800_1028, 0x80f_ffff));
+        assert_eq!(unusedp("   0x80f'ffff   0x800'1028  0xf'efd8"), None);
+    }
+
+    #[test]
+    fn test_regions_overlapping_unused() {
+        let range = AddressRange{ start: 0x0, end: 0xff };
+        let summary = PlacementSummary {
+            directives: vec![PlacementDirective{ name: "P1".into(), place_at: None, place_in: vec![range], text: String::new() }],
+            blocks: Vec::new(),
+            unused: vec![range, range, AddressRange{ start: 0x80, end: 0x17f }],
+        };
+        let regions = summary.regions();
+        assert_eq!(regions[0].free, 0x100);
+        assert_eq!(regions[0].used, 0);
+    }
+
+    #[test]
+    fn test_merged() {
+        let ranges = [
+            AddressRange{ start: 0x200, end: 0x2ff },
+            AddressRange{ start: 0x0, end: 0xff },
+            AddressRange{ start: 0x100, end: 0x17f },
+            AddressRange{ start: 0x80, end: 0x8f },
+            AddressRange{ start: 0x400, end: u64::MAX },
+        ];
+        assert_eq!(merged(&ranges), vec![
+            AddressRange{ start: 0x0, end: 0x17f },
+            AddressRange{ start: 0x200, end: 0x2ff },
+            AddressRange{ start: 0x400, end: u64::MAX },
+        ]);
+    }
+
+    #[test]
+    fn test_directivep() {
+        let directive = directivep("\"A0\":  place at address 0x800'0000 { ro section .intvec };").unwrap();
+        assert_eq!(directive.name, "A0");
+        assert_eq!(directive.place_at, Some(0x0800_0000));
+        assert_eq!(directive.place_in, vec![]);
+        assert_eq!(directivep("initialize by copy { rw };"), None);
+    }
+
+    #[test]
+    fn test_block_headerp() {
+        let p1 = block_headerp("\"P1\":                                      0xe48").unwrap();
+        assert_eq!((p1.name.as_str(), p1.part, p1.size), ("P1", None, Some(0xe48)));
+        let p2 = block_headerp("\"P2\", part 1 of 2:                            0x8").unwrap();
+        assert_eq!((p2.name.as_str(), p2.part, p2.size), ("P2", Some((1, 2)), Some(0x8)));
+        assert_eq!(block_headerp("\"P1\":  place in [from 0x0 to 0xff] { ro };"), None);
+    }
+
+    #[test]
+    fn test_placedp() {
+        let expected = PlacedSection {
+            section: "Initializer bytes".into(),
+            kind: Some(SectionKind::Const),
+            address: 0x0800_1020,
+            size: 0x8,
+            object: "<for P2-1>".into(),
+            archive: None,
+            level: 0,
+        };
+        assert_eq!(placedp("  Initializer bytes  const     0x800'1020     0x8  <for P2-1>", 2), Some(expected));
+
+        let nested = placedp("    .data            inited   0x2000'0000     0x4  main.o [1]", 2).unwrap();
+        assert_eq!(nested.section, ".data");
+        assert_eq!(nested.kind, Some(SectionKind::Inited));
+        assert_eq!(nested.object, "main.o");
+        assert_eq!(nested.archive, Some(1));
+        assert_eq!(nested.level, 1);
+    }
+
+    #[test]
+    fn test_placedp_linker_created() {
+        let placed = placedp("  .iar.init_table     const    0x080de874     0x54  - Linker created -", 2).unwrap();
+        assert_eq!(placed.section, ".iar.init_table");
+        assert_eq!(placed.object, "- Linker created -");
+        assert_eq!(placed.archive, None);
+    }
+
+    #[test]
+    fn test_parse_placements() {
+        let summary = parse_placements(SUMMARY.as_bytes()).unwrap();
+
+        let names: Vec<&str> = summary.directives.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["A0", "P1", "P2"]);
+        assert_eq!(summary.directives[2].place_in, vec![AddressRange{ start: 0x2000_0000, end: 0x2001_ffff }]);
+
+        assert_eq!(summary.blocks.len(), 4);
+        let p1 = &summary.blocks[1];
+        assert_eq!(p1.name, "P1");
+        assert_eq!(p1.size, Some(0xe48));
+        assert_eq!(p1.place_in, vec![AddressRange{ start: 0x0800_0000, end: 0x080f_ffff }]);
+        assert_eq!(p1.sections.len(), 3);
+        assert_eq!(p1.sections[1].object, "I64DivMod.o");
+        assert_eq!(p1.sections[1].archive, Some(4));
+
+        let p2 = &summary.blocks[3];
+        assert_eq!(p2.part, Some((2, 2)));
+        assert_eq!(p2.sections[0].section, "CSTACK");
+        assert_eq!(p2.sections[0].kind, None);
+        assert_eq!(p2.sections[1].kind, Some(SectionKind::Uninit));
+        assert_eq!(p2.sections[1].level, 1);
+    }
+
+    #[test]
+    fn test_regions() {
+        let summary = parse_placements(SUMMARY.as_bytes()).unwrap();
+        assert_eq!(summary.unused, vec![AddressRange{ start: 0x0800_1028, end: 0x080f_ffff }]);
+
+        let flash = MemoryRegion {
+            name: "P1".into(),
+            start: 0x0800_0000,
+            end: 0x080f_ffff,
+            used: 0x1028,
+            free: 0xf_efd8,
+        };
+        let regions = summary.regions();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0], flash);
+        assert_eq!(regions[1].name, "P2");
+        assert_eq!(regions[1].free, 0);
+    }
+
+    #[test]
+    fn test_parse_placements_malformed_row() {
+        let input = SUMMARY.replace("0x800'0ff4    0x2c", "0x800'0ff4    zz");
+        match parse_placements(input.as_bytes()) {
+            Err(Error::MalformedRow { section: "PLACEMENT SUMMARY", line: 20, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! The "runtime model attributes" library module
+
+use nom::*;
+
+use error::Error;
+use section::{linep, section_start, skip_blank_lines, split_archive_index, Warnings};
+use summary::{ModuleSummary, ObjectKey};
+
+use std::collections::BTreeMap;
+use std::str;
+
+/// The title of the runtime model attributes section
+const SECTION: &str = "RUNTIME MODEL ATTRIBUTES";
+
+/// Attribute names and their values, such as `__dlib_version` and `6`
+pub type Attributes = BTreeMap<String, String>;
+
+/// The runtime model attributes of a map file.
+///
+/// For a section
+///
+/// ```text
+/// CppFlavor       = *
+/// __SystemLibrary = DLib
+///
+/// main.o [1]:
+///     __dlib_version  = 6
+///     __CPP_Language  = C
+/// ```
+///
+/// the `image` member has the attributes in the first column, which the linker
+/// resolved for the whole image. The `objects` member has the attributes of
+/// each object, keyed by its name and archive index, since archives may have
+/// objects with the same name. Use `keyed` to compare objects across map files.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RuntimeAttributes {
+    pub image: Attributes,
+    pub objects: BTreeMap<IndexedObject, Attributes>,
+}
+
+/// Identifies an object by its name and the index of its archive, as in
+/// `main.o [1]`. Objects without an index have no archive.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct IndexedObject {
+    pub object: String,
+    pub archive: Option<usize>,
+}
+
+impl RuntimeAttributes {
+    /// Returns the attributes of each object keyed by its archive's path in the
+    /// module summary, which stays the same between map files. Archives missing
+    /// from the summary keep their index, as in `[1]`, and objects without an
+    /// archive have an empty path.
+    ///
+    /// The objects are sorted by key. Two objects have the same key when their
+    /// archives have the same path, so a key may be listed more than once.
+    pub fn keyed(&self, summary: &ModuleSummary) -> Vec<(ObjectKey, &Attributes)> {
+        let mut keyed: Vec<(ObjectKey, &Attributes)> = self
+            .objects
+            .iter()
+            .map(|(indexed, attributes)| {
+                let archive = match indexed.archive {
+                    Some(index) => summary
+                        .archive(index)
+                        .map_or_else(|| format!("[{}]", index), |table| table.path.clone()),
+                    None => String::new(),
+                };
+                (ObjectKey { archive, object: indexed.object.clone() }, attributes)
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        keyed
+    }
+}
+
+/// Splits a `key = value` line into its trimmed key and value
+fn attributep(row: &str) -> Option<(String, String)> {
+    let eq = row.find('=')?;
+    let (key, value) = (row[..eq].trim(), row[eq + 1..].trim());
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key.into(), value.into()))
+}
+
+/// Parses the `main.o [1]:` line that starts the attributes of an object
+fn objectp(row: &str) -> Option<IndexedObject> {
+    let row = row.trim_end();
+    if !row.ends_with(':') {
+        return None;
+    }
+    let (object, archive) = split_archive_index(&row[..row.len() - 1]);
+    Some(IndexedObject { object: object.into(), archive })
+}
+
+/// Parses the runtime model attributes from an IAR map file
+pub fn parse_runtime_attributes(input: &[u8]) -> Result<RuntimeAttributes, Error> {
+    parse_runtime_attributes_with(input, &mut Warnings::strict())
+}
+
+/// Parses the runtime model attributes, reporting each malformed row to the
+/// warnings
+pub fn parse_runtime_attributes_with(input: &[u8], warnings: &mut Warnings) -> Result<RuntimeAttributes, Error> {
+    let mut rest = match section_start(input, SECTION) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::MissingSection(SECTION)),
+    };
+
+    let mut attributes = RuntimeAttributes::default();
+    let mut object: Option<IndexedObject> = None;
+    while let IResult::Done(after, line) = linep(rest) {
+        if rest.is_empty() || line.starts_with(b"*") {
+            break;
+        }
+        let (row, text) = (rest, str::from_utf8(line));
+        rest = after;
+        let text = match text {
+            Ok(text) => text,
+            Err(_) => {
+                warnings.malformed_row(SECTION, input, row)?;
+                continue;
+            }
+        };
+
+        if text.trim().is_empty() {
+            // Blank lines separate the image and object attributes
+        } else if text.starts_with(char::is_whitespace) {
+            // An indented attribute belongs to the object above it
+            let owner = object.as_ref().and_then(|object| attributes.objects.get_mut(object));
+            match (attributep(text), owner) {
+                (Some((key, value)), Some(owner)) => {
+                    owner.insert(key, value);
+                }
+                _ => warnings.malformed_row(SECTION, input, row)?,
+            }
+        } else if let Some((key, value)) = attributep(text) {
+            object = None;
+            attributes.image.insert(key, value);
+        } else if let Some(indexed) = objectp(text) {
+            attributes.objects.entry(indexed.clone()).or_default();
+            object = Some(indexed);
+        } else {
+            object = None;
+            warnings.malformed_row(SECTION, input, row)?;
+        }
+    }
+
+    Ok(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    static ATTRIBUTES: &str = "*******************************************************************************
+*** RUNTIME MODEL ATTRIBUTES
+***
+
+CppFlavor       = *
+__SystemLibrary = DLib
+__dlib_version  = 6
+
+main.o [1]:
+    __CPP_Language  = C
+    __dlib_version  = 6
+
+empty.o [3]:
+
+my file.o [1]:
+    __SystemLibrary = DLib
+
+main.o [2]:
+    __CPP_Language  = C++
+
+
+*******************************************************************************
+*** MODULE SUMMARY
+***
+";
+
+    static SUMMARY: &str = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  ro data  rw data
+    ------                         -------  -------  -------
+C:\\app\\Obj: [1]
+    main.o                             120
+    my file.o                           40
+    --------------------------------------------------------
+    Total:                             160
+
+lib.a: [2]
+    main.o                              64
+    --------------------------------------------------------
+    Total:                              64
+
+------------------------------------------------------------
+    Grand Total:                       224
+
+
+*******************************************************************************";
+
+    #[test]
+    fn test_attributep() {
+        assert_eq!(attributep("__dlib_version  = 6"), Some(("__dlib_version".into(), "6".into())));
+        assert_eq!(attributep("    CppFlavor = *"), Some(("CppFlavor".into(), "*".into())));
+        assert_eq!(attributep("main.o [1]:"), None);
+        assert_eq!(attributep("a b = c"), None);
+    }
+
+    #[test]
+    fn test_parse_runtime_attributes() {
+        let attributes = parse_runtime_attributes(ATTRIBUTES.as_bytes()).unwrap();
+        assert_eq!(attributes.image.len(), 3);
+        assert_eq!(attributes.image["__SystemLibrary"], "DLib");
+
+        let objects: Vec<String> = attributes.objects.keys().map(indexed_name).collect();
+        assert_eq!(objects, vec!["empty.o [3]", "main.o [1]", "main.o [2]", "my file.o [1]"]);
+        assert_eq!(attributes.objects[&indexed("main.o", 1)]["__CPP_Language"], "C");
+        assert_eq!(attributes.objects[&indexed("main.o", 2)]["__CPP_Language"], "C++");
+        assert!(attributes.objects[&indexed("empty.o", 3)].is_empty());
+    }
+
+    #[test]
+    fn test_keyed() {
+        let attributes = parse_runtime_attributes(ATTRIBUTES.as_bytes()).unwrap();
+        let summary = ::summary::parse_module_summaries(SUMMARY.as_bytes()).unwrap();
+        let keyed = attributes.keyed(&summary);
+        let keys: Vec<String> = keyed.iter().map(|(key, _)| key.to_string()).collect();
+        assert_eq!(keys, vec!["main.o (C:\\app\\Obj)", "my file.o (C:\\app\\Obj)", "empty.o ([3])", "main.o (lib.a)"]);
+    }
+
+    #[test]
+    fn test_keyed_same_path() {
+        let mut attributes = parse_runtime_attributes(ATTRIBUTES.as_bytes()).unwrap();
+        attributes.objects.insert(indexed("main.o", 3), Attributes::new());
+        let input = SUMMARY.replace("\n------", "\nlib.a: [3]
+    main.o                              64
+    --------------------------------------------------------
+    Total:                              64
+
+------");
+        let summary = ::summary::parse_module_summaries(input.as_bytes()).unwrap();
+
+        let keyed = attributes.keyed(&summary);
+        let same: Vec<usize> = keyed.iter().filter(|(key, _)| key.to_string() == "main.o (lib.a)").map(|(_, a)| a.len()).collect();
+        assert_eq!(same, vec![1, 0]);
+    }
+
+    fn indexed(object: &str, archive: usize) -> IndexedObject {
+        IndexedObject { object: object.into(), archive: Some(archive) }
+    }
+
+    fn indexed_name(indexed: &IndexedObject) -> String {
+        format!("{} [{}]", indexed.object, indexed.archive.unwrap())
+    }
+
+    #[test]
+    fn test_parse_runtime_attributes_missing() {
+        match parse_runtime_attributes(b"*** MODULE SUMMARY\n***\n") {
+            Err(Error::MissingSection("RUNTIME MODEL ATTRIBUTES")) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_runtime_attributes_malformed_row() {
+        let input = ATTRIBUTES.replace("    __CPP_Language  = C", "    __CPP_Language");
+        match parse_runtime_attributes(input.as_bytes()) {
+            Err(Error::MalformedRow { section: "RUNTIME MODEL ATTRIBUTES", line: 10, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+}
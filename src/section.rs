@@ -0,0 +1,314 @@
+//! Parsers shared by the sections of a map file
+//!
+//! Each section of a map file starts with a header such as
+//!
+//! ```text
+//! *******************************************************************************
+//! *** ENTRY LIST
+//! ***
+//! ```
+
+use nom::*;
+
+use error::Error;
+
+/// Parses the many ***** that deliminate sections
+named!(pub stars, take_while!(|c| c == b'*'));
+
+/// Parses the entire header of the section with the title, returning the title
+pub fn section_header<'a>(input: &'a [u8], title: &str) -> IResult<&'a [u8], &'a [u8]> {
+    do_parse!(input,
+        stars >> line_ending >> stars >> space >> title: tag!(title) >> line_ending >> stars >>
+            (title)
+    )
+}
+
+/// Parses everything up to and including the header of the section with the
+/// title, and the line ending that follows it
+pub fn section_start<'a>(input: &'a [u8], title: &str) -> IResult<&'a [u8], &'a [u8]> {
+    do_parse!(input,
+        many_till!(call!(anychar), apply!(section_header, title)) >> line_ending >> (&[])
+    )
+}
+
+/// Parses any number of blank lines
+named!(pub blank_lines,
+    map!(
+        many0!(do_parse!(opt!(space) >> line_ending >> (()))),
+        |_| &[][..]
+    )
+);
+
+/// Returns the input that follows any blank lines
+pub fn skip_blank_lines(input: &[u8]) -> &[u8] {
+    match blank_lines(input) {
+        IResult::Done(rest, _) => rest,
+        _ => input,
+    }
+}
+
+/// Parses a line, including its line ending if there is one
+named!(pub linep,
+    do_parse!(
+        line: not_line_ending >>
+        opt!(complete!(line_ending)) >>
+        (line)
+    )
+);
+
+/// Parses a column header that starts with the word, possibly indented, and the
+/// ------- underline beneath it, returning the column header
+pub fn column_header<'a>(input: &'a [u8], first_word: &str) -> IResult<&'a [u8], &'a [u8]> {
+    do_parse!(input,
+        peek!(do_parse!(opt!(space) >> tag!(first_word) >> (()))) >>
+        header: linep >>
+        peek!(do_parse!(opt!(space) >> char!('-') >> (()))) >>
+        linep >>
+        (header)
+    )
+}
+
+/// Splits a line that starts with a `"quoted"` name, possibly indented, into
+/// the name and the text after it
+pub fn quoted_name(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if !text.starts_with('"') {
+        return None;
+    }
+    let close = text[1..].find('"')? + 1;
+    Some((&text[1..close], &text[close + 1..]))
+}
+
+/// Applies a parser for an optional row, advancing the input if-and-only-if
+/// the parser succeeds
+pub fn optional_row<'a, O, F>(input: &mut &'a [u8], parser: F) -> Option<O>
+where
+    F: FnOnce(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    match parser(input) {
+        IResult::Done(rest, o) => {
+            *input = rest;
+            Some(o)
+        }
+        _ => None,
+    }
+}
+
+/// Parses a hexadecimal number such as `0x080e499d`, or `0x0800'1a2d` with the
+/// digit separators of newer linkers
+pub fn parse_hex(word: &str) -> Option<u64> {
+    if !word.starts_with("0x") {
+        return None;
+    }
+    let digits: String = word[2..].chars().filter(|c| *c != '\'').collect();
+    u64::from_str_radix(&digits, 16).ok()
+}
+
+/// Splits an object such as `main.o [1]` into the object name and the index of
+/// its archive. Objects without an index, such as `- Linker created -`, have
+/// no archive.
+pub fn split_archive_index(object: &str) -> (&str, Option<usize>) {
+    let object = object.trim();
+    if object.ends_with(']') {
+        if let Some(open) = object.rfind(" [") {
+            if let Ok(index) = object[open + 2..object.len() - 1].parse() {
+                return (object[..open].trim_end(), Some(index));
+            }
+        }
+    }
+    (object, None)
+}
+
+/// Where the parser of a section reports its malformed rows. A strict parse
+/// stops at the first malformed row, and a lenient parse records the row and
+/// skips it. The lines of the input are indexed at the first malformed row, so
+/// that each row is numbered without counting the lines before it again.
+pub struct Warnings<'a> {
+    sink: Option<&'a mut Vec<Error>>,
+    lines: Option<Lines>,
+}
+
+impl<'a> Warnings<'a> {
+    /// Reports every malformed row as an error
+    pub fn strict() -> Warnings<'a> {
+        Warnings { sink: None, lines: None }
+    }
+
+    /// Records every malformed row in the sink
+    pub fn lenient(sink: &'a mut Vec<Error>) -> Warnings<'a> {
+        Warnings { sink: Some(sink), lines: None }
+    }
+
+    /// Reports a malformed row that starts `row`, a suffix of `input`. Returns
+    /// the error if the parse is strict, so that the parser stops. Otherwise
+    /// the parser skips the row.
+    pub fn malformed_row(&mut self, section: &'static str, input: &[u8], row: &[u8]) -> Result<(), Error> {
+        let line = match self.lines {
+            Some(ref lines) if lines.len == input.len() => lines.line(row),
+            _ => {
+                let lines = Lines::new(input);
+                let line = lines.line(row);
+                self.lines = Some(lines);
+                line
+            }
+        };
+        self.report(Error::malformed_row_at(section, line, row))
+    }
+
+    /// Reports a malformed row's error, as `malformed_row` does
+    pub fn report(&mut self, err: Error) -> Result<(), Error> {
+        match self.sink {
+            Some(ref mut sink) => {
+                sink.push(err);
+                Ok(())
+            }
+            None => Err(err),
+        }
+    }
+}
+
+/// The line numbers of an input. Finds the line that starts a suffix of the
+/// input without counting the lines before it each time.
+pub struct Lines {
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl Lines {
+    /// Indexes the lines of the input
+    pub fn new(input: &[u8]) -> Lines {
+        let mut starts = vec![0];
+        starts.extend(input.iter().enumerate().filter(|&(_, c)| *c == b'\n').map(|(i, _)| i + 1));
+        Lines { starts, len: input.len() }
+    }
+
+    /// Returns the 1-based line number of the line that contains the start of
+    /// `rest`, a suffix of the indexed input
+    pub fn line(&self, rest: &[u8]) -> usize {
+        let offset = self.len - rest.len();
+        match self.starts.binary_search(&offset) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        }
+    }
+}
+
+/// Splits the text into whitespace separated words, each with its byte offset
+/// in the text
+pub fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    static EMPTY: &[u8] = b"";
+
+    #[test]
+    fn test_section_start() {
+        let input = "some other content
+
+*******************************************************************************
+*** ENTRY LIST
+***
+"
+            .as_bytes();
+
+        assert_eq!(section_start(input, "ENTRY LIST"), IResult::Done(EMPTY, EMPTY));
+        assert!(!section_start(input, "INIT TABLE").is_done());
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("0x080e499d"), Some(0x080e_499d));
+        assert_eq!(parse_hex("0x0800'1a2d"), Some(0x0800_1a2d));
+        assert_eq!(parse_hex("0x2a"), Some(0x2a));
+        assert_eq!(parse_hex("42"), None);
+        assert_eq!(parse_hex("0xzz"), None);
+    }
+
+    #[test]
+    fn test_split_archive_index() {
+        assert_eq!(split_archive_index("main.o [1]"), ("main.o", Some(1)));
+        assert_eq!(split_archive_index("my file.o [12] "), ("my file.o", Some(12)));
+        assert_eq!(split_archive_index("- Linker created -"), ("- Linker created -", None));
+        assert_eq!(split_archive_index("<for P2-1>"), ("<for P2-1>", None));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let input = b"row\nbad\n";
+        assert!(Warnings::strict().malformed_row("SECTION", input, &input[4..]).is_err());
+
+        let mut sink = Vec::new();
+        assert!(Warnings::lenient(&mut sink).malformed_row("SECTION", input, &input[4..]).is_ok());
+        let mut warnings = Warnings::lenient(&mut sink);
+        assert!(warnings.malformed_row("SECTION", input, &input[4..]).is_ok());
+        assert!(warnings.malformed_row("SECTION", input, &input[..]).is_ok());
+        let lines: Vec<(usize, &str)> = sink
+            .iter()
+            .map(|err| match *err {
+                Error::MalformedRow { section: "SECTION", line, ref text } => (line, text.as_str()),
+                _ => panic!("{:?}", err),
+            })
+            .collect();
+        assert_eq!(lines, vec![(2, "bad"), (2, "bad"), (1, "row")]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let input = b"one\ntwo\n\nfour";
+        let lines = Lines::new(input);
+        assert_eq!(lines.line(input), 1);
+        assert_eq!(lines.line(&input[4..]), 2);
+        assert_eq!(lines.line(&input[6..]), 2);
+        assert_eq!(lines.line(&input[8..]), 3);
+        assert_eq!(lines.line(&input[9..]), 4);
+        assert_eq!(lines.line(b""), 4);
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words("  ?main   0x080e499d  Code"), vec![(2, "?main"), (10, "0x080e499d"), (22, "Code")]);
+        assert_eq!(words(""), vec![]);
+    }
+
+    #[test]
+    fn test_column_header() {
+        let input = b"  Address      Size\n  -------      ----\nrow";
+        assert_eq!(column_header(input, "Address"), IResult::Done(&b"row"[..], &b"  Address      Size"[..]));
+        assert!(!column_header(input, "Entry").is_done());
+        assert!(!column_header(b"Entry  Address\nrow\n", "Entry").is_done());
+    }
+
+    #[test]
+    fn test_quoted_name() {
+        assert_eq!(quoted_name("  \"main\": 0x0800'01d9"), Some(("main", ": 0x0800'01d9")));
+        assert_eq!(quoted_name("\"P1\":"), Some(("P1", ":")));
+        assert_eq!(quoted_name("main"), None);
+        assert_eq!(quoted_name("\"unterminated"), None);
+    }
+
+    #[test]
+    fn test_linep() {
+        assert_eq!(linep(b"first\nsecond"), IResult::Done(&b"second"[..], &b"first"[..]));
+        assert_eq!(linep(b"last"), IResult::Done(EMPTY, &b"last"[..]));
+    }
+}
@@ -0,0 +1,288 @@
+//! The "stack usage" library module
+
+use nom::*;
+
+use error::Error;
+use section::{column_header, linep, parse_hex, quoted_name, section_start, skip_blank_lines, words, Warnings};
+
+use std::str;
+
+/// The title of the stack usage section
+const SECTION: &str = "STACK USAGE";
+
+/// A row of the call graph root category table, such as
+///
+/// ```text
+///   Call Graph Root Category  Max Use  Total Use
+///   ------------------------  -------  ---------
+///   interrupt                    104        312
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StackCategory {
+    pub name: String,
+    pub max_use: u64,
+    pub total_use: u64,
+}
+
+/// A function of a call chain, and the size of its stack frame
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StackFrame {
+    pub function: String,
+    pub size: u64,
+}
+
+/// A call graph root.
+///
+/// For a root
+///
+/// ```text
+/// interrupt
+///   "SysTick_Handler": 0x0800'0329
+///
+///   Maximum call chain                            8  bytes
+///
+///     "SysTick_Handler"                            8
+///     "HAL_IncTick"                                0
+/// ```
+///
+/// the `category` is `interrupt`, the `function` is `SysTick_Handler`, and the
+/// `max_depth` is `Some(8)`. The `call_chain` is the chain that uses the most
+/// stack, starting at the root. A root whose depth the linker could not
+/// compute has no `max_depth`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StackRoot {
+    pub category: String,
+    pub function: String,
+    pub address: Option<u64>,
+    pub max_depth: Option<u64>,
+    pub call_chain: Vec<StackFrame>,
+}
+
+/// The stack usage of a map file linked with `--stack_usage`
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct StackUsage {
+    pub categories: Vec<StackCategory>,
+    pub roots: Vec<StackRoot>,
+}
+
+impl StackUsage {
+    /// Returns the root for the function
+    pub fn root(&self, function: &str) -> Option<&StackRoot> {
+        self.roots.iter().find(|root| root.function == function)
+    }
+}
+
+/// Parses a row of the category table
+fn categoryp(row: &str) -> Option<StackCategory> {
+    let words = words(row);
+    match words[..] {
+        [(first, _), .., (at, max_use), (_, total_use)] => Some(StackCategory {
+            name: row[first..at].trim_end().into(),
+            max_use: max_use.parse().ok()?,
+            total_use: total_use.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+/// Parses the `"SysTick_Handler": 0x0800'0329` line of a root
+fn root_functionp(row: &str) -> Option<(&str, Option<u64>)> {
+    let (function, rest) = quoted_name(row)?;
+    let rest = rest.trim();
+    if !rest.starts_with(':') {
+        return None;
+    }
+    match rest[1..].trim() {
+        "" => Some((function, None)),
+        address => Some((function, Some(parse_hex(address)?))),
+    }
+}
+
+/// Parses the `Maximum call chain    8  bytes` line of a root. The depth is
+/// `None` if it is not a number, as when the linker cannot compute it.
+fn max_depthp(row: &str) -> Option<Option<u64>> {
+    let row = row.trim_start();
+    if !row.starts_with("Maximum call chain") {
+        return None;
+    }
+    Some(words(row).get(3).and_then(|&(_, depth)| depth.parse().ok()))
+}
+
+/// Parses a `"HAL_IncTick"    0` frame of a call chain
+fn framep(row: &str) -> Option<StackFrame> {
+    let (function, rest) = quoted_name(row)?;
+    let size = words(rest).last()?.1.parse().ok()?;
+    Some(StackFrame {
+        function: function.into(),
+        size,
+    })
+}
+
+/// Parses the stack usage from an IAR map file
+pub fn parse_stack(input: &[u8]) -> Result<StackUsage, Error> {
+    parse_stack_with(input, &mut Warnings::strict())
+}
+
+/// Parses the stack usage, reporting each malformed row to the warnings
+pub fn parse_stack_with(input: &[u8], warnings: &mut Warnings) -> Result<StackUsage, Error> {
+    let mut rest = match section_start(input, SECTION) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::MissingSection(SECTION)),
+    };
+
+    rest = match column_header(rest, "Call Graph Root") {
+        IResult::Done(rest, _) => rest,
+        _ => return Err(Error::malformed_column_header(SECTION, input, rest)),
+    };
+
+    // The category table ends at the first blank line
+    let mut usage = StackUsage::default();
+    while let IResult::Done(after, line) = linep(rest) {
+        let text = str::from_utf8(line);
+        if text.is_ok_and(|text| text.trim().is_empty()) {
+            break;
+        }
+        match text.ok().and_then(categoryp) {
+            Some(category) => usage.categories.push(category),
+            None => warnings.malformed_row(SECTION, input, rest)?,
+        }
+        rest = after;
+    }
+
+    // Each root starts with its category in the first column
+    let mut category: Option<&str> = None;
+    while let IResult::Done(after, line) = linep(rest) {
+        if rest.is_empty() || line.starts_with(b"*") {
+            break;
+        }
+        let (row, text) = (rest, str::from_utf8(line));
+        rest = after;
+        let text = match text {
+            Ok(text) => text,
+            Err(_) => {
+                warnings.malformed_row(SECTION, input, row)?;
+                continue;
+            }
+        };
+
+        if text.trim().is_empty() {
+            // Blank lines separate the parts of a root
+        } else if !text.starts_with(char::is_whitespace) {
+            category = Some(text.trim_end());
+        } else if let (Some((function, address)), Some(category)) = (root_functionp(text), category) {
+            usage.roots.push(StackRoot {
+                category: category.into(),
+                function: function.into(),
+                address,
+                max_depth: None,
+                call_chain: Vec::new(),
+            });
+        } else {
+            match (max_depthp(text), framep(text), usage.roots.last_mut()) {
+                (Some(depth), _, Some(root)) => root.max_depth = depth,
+                (None, Some(frame), Some(root)) => root.call_chain.push(frame),
+                _ => warnings.malformed_row(SECTION, input, row)?,
+            }
+        }
+    }
+
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    static USAGE: &str = "*******************************************************************************
+*** STACK USAGE
+***
+
+  Call Graph Root Category  Max Use  Total Use
+  ------------------------  -------  ---------
+  interrupt                      8          8
+  Program entry                224        224
+
+
+Program entry
+  \"__iar_program_start\": 0x0800'01d9
+
+  Maximum call chain                           224  bytes
+
+    \"__iar_program_start\"                        0
+    \"__cmain\"                                    0
+    \"main\"                                      16
+    \"printf\"                                   208
+
+interrupt
+  \"SysTick_Handler\": 0x0800'0329
+
+  Maximum call chain                             8  bytes
+
+    \"SysTick_Handler\"                            8
+    \"HAL_IncTick\"                                0
+
+
+*******************************************************************************
+*** MODULE SUMMARY
+***
+";
+
+    #[test]
+    fn test_categoryp() {
+        let expected = StackCategory{ name: "Program entry".into(), max_use: 224, total_use: 224 };
+        assert_eq!(categoryp("  Program entry                224        224"), Some(expected));
+        assert_eq!(categoryp("  interrupt"), None);
+    }
+
+    #[test]
+    fn test_root_functionp() {
+        assert_eq!(root_functionp("  \"main\": 0x0800'01d9"), Some(("main", Some(0x0800_01d9))));
+        assert_eq!(root_functionp("    \"main\"     16"), None);
+    }
+
+    #[test]
+    fn test_max_depthp() {
+        assert_eq!(max_depthp("  Maximum call chain      224  bytes"), Some(Some(224)));
+        assert_eq!(max_depthp("  Maximum call chain      ?"), Some(None));
+        assert_eq!(max_depthp("    \"main\"     16"), None);
+    }
+
+    #[test]
+    fn test_parse_stack() {
+        let usage = parse_stack(USAGE.as_bytes()).unwrap();
+        assert_eq!(usage.categories.len(), 2);
+        assert_eq!(usage.categories[0].name, "interrupt");
+
+        assert_eq!(usage.roots.len(), 2);
+        let entry = &usage.roots[0];
+        assert_eq!(entry.category, "Program entry");
+        assert_eq!(entry.function, "__iar_program_start");
+        assert_eq!(entry.address, Some(0x0800_01d9));
+        assert_eq!(entry.max_depth, Some(224));
+        assert_eq!(entry.call_chain.len(), 4);
+        assert_eq!(entry.call_chain[3], StackFrame{ function: "printf".into(), size: 208 });
+
+        let systick = usage.root("SysTick_Handler").unwrap();
+        assert_eq!(systick.category, "interrupt");
+        assert_eq!(systick.max_depth, Some(8));
+        assert!(usage.root("printf").is_none());
+    }
+
+    #[test]
+    fn test_parse_stack_missing() {
+        match parse_stack(b"*** MODULE SUMMARY\n***\n") {
+            Err(Error::MissingSection("STACK USAGE")) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_stack_malformed_row() {
+        let input = USAGE.replace("\"main\"                                      16", "\"main\"  sixteen");
+        match parse_stack(input.as_bytes()) {
+            Err(Error::MalformedRow { section: "STACK USAGE", line: 18, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+}
@@ -1,13 +1,21 @@
 //! The "module summary" library module
 
 mod module;
+mod validate;
 
 use nom::*;
 
-use self::module::module_table;
-pub use self::module::Module;
+use self::module::{labelled_rowp, module_table, Layout};
+pub use self::module::{Module, ModuleDelta, ModuleRow};
+pub use self::validate::{TotalMismatch, TotalRow};
+
+use error::Error;
+use section::{optional_row, section_start, skip_blank_lines, split_archive_index, Lines, Warnings};
+#[cfg(test)]
+use section::section_header;
 
 use std::collections::HashMap;
+use std::fmt;
 use std::str;
 
 /// Relates an object dir to a map of files.
@@ -24,49 +32,160 @@ use std::str;
 ///     Total:                              55      99       22
 /// ```
 ///
-/// The `name` member is `C:\Projects\A\Obj: [1]`, and the `table` member
-/// is a `HashMap` with the object name as the key, and a `Module` as the value.
+/// The `name` member is `C:\Projects\A\Obj: [1]`, the `path` member is
+/// `C:\Projects\A\Obj`, and the `index` member is `Some(1)`. Objects elsewhere
+/// in the map file, such as `Bar.o [1]`, refer to the archive by its `index`.
+/// The `line` member is the line of the map file that names the archive. The
+/// `table` member has a `ModuleRow` for each object, in the order of the map
+/// file.
+/// The `total` member is the `Total:` row computed by the linker. An archive
+/// with an empty `Total:` row has a `total` with every field `None`.
 #[derive(Debug, PartialEq)]
 pub struct ObjModuleTable {
     pub name: String,
-    pub table: HashMap<String, Module>,
+    pub path: String,
+    pub index: Option<usize>,
+    pub line: usize,
+    pub table: Vec<ModuleRow>,
+    pub total: Module,
+}
+
+impl ObjModuleTable {
+    /// Creates a table for the archive named by the `name` header, splitting
+    /// the archive's path from its index
+    fn new(name: String, line: usize, table: Vec<ModuleRow>, total: Module) -> ObjModuleTable {
+        let (path, index) = split_archive_index(&name);
+        let path = path.trim_end_matches(':').into();
+        ObjModuleTable { name, path, index, line, table, total }
+    }
+
+    /// Returns the sizes of the first object with the name
+    pub fn get(&self, name: &str) -> Option<&Module> {
+        self.table.iter().find(|row| row.name == name).map(|row| &row.module)
+    }
+}
+
+/// The complete module summary of a map file.
+///
+/// In addition to the per-archive tables, the module summary ends with rows
+/// that the linker computes for the whole image:
+///
+/// ```text
+///     Gaps                                96       90        9
+///     Linker created                               88  378 432
+/// ------------------------------------------------------------
+///     Grand Total:                   492 776  630 240  591 176
+/// ```
+///
+/// Each row is optional, since the linker omits `Gaps` and `Linker created`
+/// when they are empty.
+#[derive(Debug, PartialEq)]
+pub struct ModuleSummary {
+    pub tables: Vec<ObjModuleTable>,
+    pub gaps: Option<Module>,
+    pub linker_created: Option<Module>,
+    pub grand_total: Option<Module>,
+}
+
+/// Identifies an object by its archive's path and its name, since archives
+/// may have objects with the same name. The archive's index is not part of the
+/// key, since it changes between map files.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ObjectKey {
+    pub archive: String,
+    pub object: String,
+}
+
+impl fmt::Display for ObjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.object, self.archive)
+    }
+}
+
+/// An object listed more than once in the same archive, and the lines of its
+/// rows
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Duplicate {
+    pub key: ObjectKey,
+    pub lines: Vec<usize>,
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.lines.iter().map(|line| line.to_string()).collect();
+        write!(f, "{} is listed {} times, on lines {}", self.key, self.lines.len(), lines.join(", "))
+    }
 }
 
-/// Parses the many ***** that deliminate sections
-named!(stars, take_while!(|c| c == '*' as u8));
+impl ModuleSummary {
+    /// Returns the archive with the index, as in the `[1]` of `Bar.o [1]`
+    pub fn archive(&self, index: usize) -> Option<&ObjModuleTable> {
+        self.tables.iter().find(|table| table.index == Some(index))
+    }
+
+    /// Returns every object row and its key, in the order of the map file
+    pub fn objects(&self) -> Vec<(ObjectKey, &ModuleRow)> {
+        self.tables
+            .iter()
+            .flat_map(|table| {
+                table.table.iter().map(move |row| {
+                    let key = ObjectKey { archive: table.path.clone(), object: row.name.clone() };
+                    (key, row)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the objects listed more than once in the same archive, in the
+    /// order of their first row. Archives with the same path are treated as
+    /// one archive.
+    pub fn duplicates(&self) -> Vec<Duplicate> {
+        let mut duplicates: Vec<Duplicate> = Vec::new();
+        let mut seen: HashMap<ObjectKey, usize> = HashMap::new();
+        for (key, row) in self.objects() {
+            let at = *seen.entry(key.clone()).or_insert_with(|| {
+                duplicates.push(Duplicate { key, lines: Vec::new() });
+                duplicates.len() - 1
+            });
+            duplicates[at].lines.push(row.line);
+        }
+        duplicates.retain(|duplicate| duplicate.lines.len() > 1);
+        duplicates
+    }
+}
 
-/// Parses the module summary header name
-named!(module_summary, tag!("MODULE SUMMARY"));
+/// The title of the module summary section
+const SECTION: &str = "MODULE SUMMARY";
 
 /// Parses the entire module summary header, returning the header name
-named!(
-    header,
-    do_parse!(
-        stars >> line_ending >> stars >> space >> summary: module_summary >> line_ending >> stars >>
-            (summary)
-    )
-);
+#[cfg(test)]
+fn header(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    section_header(input, SECTION)
+}
 
-/// Parses the table start. Consumes the table start and returns empty bytes.
+/// Parses the table start, deriving the table layout from the column header
+/// and its underline. Consumes the table start, but not its line ending.
 named!(
-    table_start<usize>,
-    do_parse!(
-        nbytes: do_parse!(
-            leading: multispace >>
-            tag!("Module") >>
-            trailing: multispace >>
-            (leading.len() + trailing.len() + "Module".len())) >>
-        tag!("ro code") >> space >>
-        tag!("ro data") >> space >> tag!("rw data") >> line_ending >>
-        take_while!(|x| (x == ' ' as u8) || (x == '-' as u8)) >> (nbytes)
+    table_start<Layout>,
+    map_opt!(
+        do_parse!(
+            header: not_line_ending >> line_ending >>
+            underline: take_while!(|x| (x == b' ') || (x == b'-')) >>
+            (header, underline)
+        ),
+        |(header, underline)| Layout::from_header(header, underline)
     )
 );
 
-/// Parses the object file directory
+/// Parses the object file directory. The directory starts in the first column,
+/// which distinguishes it from the indented summary rows.
 named!(obj_header<String>,
     map!(
-        do_parse!(bs: not_line_ending >> line_ending >> (bs)),
-        |bs| str::from_utf8(bs).unwrap().into()
+        map_res!(
+            do_parse!(peek!(none_of!(" \t\r\n")) >> bs: not_line_ending >> line_ending >> (bs)),
+            str::from_utf8
+        ),
+        |s: &str| s.into()
     )
 );
 
@@ -80,28 +199,96 @@ named!(table_end,
     )
 );
 
-/// Parse module tables
-fn tablep(input: &[u8], nbytes: usize) -> IResult<&[u8], ObjModuleTable> {
+/// Parses a rule of ------------------- that starts in the first column
+named!(grand_total_rule,
+    do_parse!(
+        many1!(char!('-')) >>
+        line_ending >>
+        (&[])
+    )
+);
+
+/// Parse module tables. `lines` indexes the map file, and numbers the rows.
+fn tablep<'a>(input: &'a [u8], layout: &Layout, lines: &Lines, warnings: &mut Warnings) -> IResult<&'a [u8], ObjModuleTable> {
     do_parse!(input,
         obj: obj_header >>
-        ms: apply!(module_table, nbytes) >>
+        ms: apply!(module_table, layout, lines, warnings) >>
         table_end >>
-        take_until_and_consume!("\n") >>
-        (ObjModuleTable{ name: obj, table: ms })
+        total: apply!(labelled_rowp, "Total:", layout) >>
+        (ObjModuleTable::new(obj, lines.line(input), ms, total))
     )
 }
 
-/// Parse the module summary table from an IAR map file
-named!(pub parse_module_summaries< &[u8], Vec<ObjModuleTable> >,
-    do_parse!(
-        many_till!(anychar, header) >> line_ending >> line_ending >>
-        nbytes: table_start >> line_ending >>
-        ts: many0!(do_parse!(t: apply!(tablep, nbytes) >> multispace >> (t))) >>
-        take_until!("*") >>
-        (ts)
-    )
+/// Parses the table start and its line ending, returning the table layout
+named!(columnsp<Layout>,
+    do_parse!(layout: table_start >> line_ending >> (layout))
 );
 
+/// Locates the row that stopped the table parser. If the input starts with an
+/// object file directory, the table is re-parsed row by row to find the
+/// offending row. Otherwise, the input itself is the offending row.
+fn stopping_row<'a>(input: &'a [u8], layout: &Layout, lines: &Lines) -> &'a [u8] {
+    let rest = match obj_header(input) {
+        IResult::Done(rest, _) => rest,
+        _ => return input,
+    };
+    let rest = match module_table(rest, layout, lines, &mut Warnings::strict()) {
+        IResult::Done(rest, _) => rest,
+        _ => return rest,
+    };
+    match table_end(rest) {
+        IResult::Done(rest, _) => rest,
+        _ => rest,
+    }
+}
+
+/// Parse the module summary table from an IAR map file
+pub fn parse_module_summaries(input: &[u8]) -> Result<ModuleSummary, Error> {
+    parse_module_summaries_with(input, &mut Warnings::strict())
+}
+
+/// Parse the module summary table, reporting each malformed row to the
+/// warnings
+pub fn parse_module_summaries_with(input: &[u8], warnings: &mut Warnings) -> Result<ModuleSummary, Error> {
+    let rest = match section_start(input, SECTION) {
+        IResult::Done(rest, _) => skip_blank_lines(rest),
+        _ => return Err(Error::MissingSection(SECTION)),
+    };
+
+    let (mut rest, layout) = match columnsp(rest) {
+        IResult::Done(rest, layout) => (rest, layout),
+        _ => {
+            return Err(if rest.trim_ascii_start().starts_with(b"Module") {
+                Error::unsupported_layout(SECTION, input, rest)
+            } else {
+                Error::malformed_column_header(SECTION, input, rest)
+            });
+        }
+    };
+
+    let lines = Lines::new(input);
+    let mut tables = Vec::new();
+    while let IResult::Done(after, table) = tablep(rest, &layout, &lines, warnings) {
+        tables.push(table);
+        rest = skip_blank_lines(after);
+    }
+
+    let gaps = optional_row(&mut rest, |i| labelled_rowp(i, "Gaps", &layout));
+    let linker_created = optional_row(&mut rest, |i| labelled_rowp(i, "Linker created", &layout));
+    optional_row(&mut rest, grand_total_rule);
+    let grand_total = optional_row(&mut rest, |i| labelled_rowp(i, "Grand Total:", &layout));
+
+    // Without a Grand Total, the summary must end at the next section, or the
+    // table parser stopped early on a row it did not understand.
+    let rest = skip_blank_lines(rest);
+    if grand_total.is_none() && !rest.is_empty() && rest[0] != b'*' {
+        let row = skip_blank_lines(stopping_row(rest, &layout, &lines));
+        return Err(Error::malformed_row(SECTION, input, row));
+    }
+
+    Ok(ModuleSummary{ tables, gaps, linker_created, grand_total })
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -127,7 +314,7 @@ mod tests {
     ------                         -------  -------  -------"
             .as_bytes();
 
-        assert_eq!(table_start(h), Done(EMPTY, 35));
+        assert_eq!(table_start(h), Done(EMPTY, Layout::standard(35)));
     }
 
     #[test]
@@ -136,42 +323,62 @@ mod tests {
         assert_eq!(obj_header(h), Done(EMPTY, "C:\\proj\\A".into()));
     }
 
+    #[test]
+    fn test_complete_parse_table_with_spaces() {
+        let table = "C:\\Program Files\\IAR\\rt7M_tl.a: [4]
+    my file.o                          638      112
+    --------------------------------------------------------
+    Total:                             638      112\n"
+            .as_bytes();
+
+        let mut expected_map: Vec<(&str, Module)> = Vec::new();
+        let module = Module{ ro_code: Some(638), ro_data: Some(112), rw_data: None, ..Default::default() };
+        expected_map.push(("my file.o", module.clone()));
+
+        let name: String = "C:\\Program Files\\IAR\\rt7M_tl.a: [4]".into();
+        assert_eq!(tablep(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict()),
+            IResult::Done(EMPTY, ObjModuleTable::new(name, 1, ModuleRow::consecutive(expected_map, 2), module)));
+    }
+
     #[test]
     fn test_module_table_three_row_with_ending() {
         let table = b"                        Foo.o          724      544\n               Bar.o                   532      569      103\n            Baz.o                                88       16\n        --------------------------------------------------------\n    some other garbage";
 
-        let mut expected: HashMap<String, Module> = HashMap::new();
+        let mut expected: Vec<(&str, Module)> = Vec::new();
         let rest = b"        --------------------------------------------------------\n    some other garbage";
 
-        expected.insert(
-            "Foo.o".into(),
+        expected.push((
+            "Foo.o",
             Module {
                 ro_code: Some(724),
                 ro_data: Some(544),
                 rw_data: None,
+                ..Default::default()
             },
-        );
+        ));
 
-        expected.insert(
-            "Bar.o".into(),
+        expected.push((
+            "Bar.o",
             Module {
                 ro_code: Some(532),
                 ro_data: Some(569),
                 rw_data: Some(103),
+                ..Default::default()
             },
-        );
+        ));
 
-        expected.insert(
-            "Baz.o".into(),
+        expected.push((
+            "Baz.o",
             Module {
                 ro_code: None,
                 ro_data: Some(88),
                 rw_data: Some(16),
+                ..Default::default()
             },
-        );
+        ));
 
-        let result = module_table(table, 35);
-        assert_eq!(result, IResult::Done(&rest[..], expected));
+        let result = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
+        assert_eq!(result, IResult::Done(&rest[..], ModuleRow::consecutive(expected, 1)));
     }
 
     #[test]
@@ -180,9 +387,9 @@ mod tests {
     --------------------------------------------------------
     Total:\n"
             .as_bytes();
-        let expected_map: HashMap<String, Module> = HashMap::new();
-        let result = tablep(input, 35);
-        assert_eq!(result, IResult::Done(EMPTY, ObjModuleTable{ name: "command line: [2]".into(), table: expected_map }));
+        let result = tablep(input, &Layout::standard(35), &Lines::new(input), &mut Warnings::strict());
+        let total = Module{ ro_code: None, ro_data: None, rw_data: None, ..Default::default() };
+        assert_eq!(result, IResult::Done(EMPTY, ObjModuleTable::new("command line: [2]".into(), 1, vec![], total)));
     }
 
     #[test]
@@ -196,52 +403,246 @@ mod tests {
     Total:                           5 700      244      240\n"
             .as_bytes();
 
-        let mut exected_map: HashMap<String, Module> = HashMap::new();
-
-        exected_map.insert(
-            "libFoos.o".into(),
-            Module {
-                ro_code: Some(6258),
-                ro_data: Some(300),
-                rw_data: None,
-            },
-        );
-
-        exected_map.insert(
-            "libBars.o".into(),
-            Module {
-                ro_code: Some(638),
-                ro_data: Some(112),
-                rw_data: None,
-            },
-        );
-
-        exected_map.insert(
-            "libBaz.o".into(),
-            Module {
-                ro_code: None,
-                ro_data: Some(768),
-                rw_data: Some(768),
-            },
-        );
-
-        exected_map.insert(
-            "libHey.o".into(),
-            Module {
-                ro_code: Some(122),
-                ro_data: None,
-                rw_data: None,
-            },
-        );
+        let exected_map: Vec<(&str, Module)> = vec![
+            (
+                "libFoos.o",
+                Module {
+                    ro_code: Some(6258),
+                    ro_data: Some(300),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+            (
+                "libBars.o",
+                Module {
+                    ro_code: Some(638),
+                    ro_data: Some(112),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+            (
+                "libBaz.o",
+                Module {
+                    ro_code: None,
+                    ro_data: Some(768),
+                    rw_data: Some(768),
+                    ..Default::default()
+                },
+            ),
+            (
+                "libHey.o",
+                Module {
+                    ro_code: Some(122),
+                    ro_data: None,
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+        ];
 
         let expected_obj_name: String = "myarchive.a: [6]".into();
 
-        let result = tablep(table, 35);
+        let expected_total = Module{ ro_code: Some(5_700), ro_data: Some(244), rw_data: Some(240), ..Default::default() };
+
+        let result = tablep(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
         if let &Done(remaining, _) = &result {
             println!("{}", str::from_utf8(remaining).unwrap());
         }
+        let rows = ModuleRow::consecutive(exected_map, 2);
         assert_eq!(result,
-            IResult::Done(EMPTY, ObjModuleTable{ name: expected_obj_name, table: exected_map }));
+            IResult::Done(EMPTY, ObjModuleTable::new(expected_obj_name, 1, rows, expected_total)));
+    }
+
+    #[test]
+    fn test_parse_module_summaries_totals() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  ro data  rw data
+    ------                         -------  -------  -------
+FileSys.a: [3]
+    FAT_Dir.o                          536       24
+    --------------------------------------------------------
+    Total:                             536       24
+
+    Gaps                                 2
+------------------------------------------------------------
+    Grand Total:                       538       24
+
+
+*******************************************************************************"
+            .as_bytes();
+
+        let result = parse_module_summaries(input);
+        if let Ok(summary) = result {
+            assert_eq!(summary.tables.len(), 1);
+            assert_eq!(summary.tables[0].total, Module{ ro_code: Some(536), ro_data: Some(24), rw_data: None, ..Default::default() });
+            assert_eq!(summary.gaps, Some(Module{ ro_code: Some(2), ro_data: None, rw_data: None, ..Default::default() }));
+            assert_eq!(summary.linker_created, None);
+            assert_eq!(summary.grand_total, Some(Module{ ro_code: Some(538), ro_data: Some(24), rw_data: None, ..Default::default() }));
+        } else {
+            panic!("{:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_parse_module_summaries_duplicates() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  ro data  rw data
+    ------                         -------  -------  -------
+vendor.a: [1]
+    crc.o                               40
+    util.o                              12
+    crc.o                               48
+    --------------------------------------------------------
+    Total:                             100
+
+inhouse.a: [2]
+    crc.o                               64
+    --------------------------------------------------------
+    Total:                              64
+
+------------------------------------------------------------
+    Grand Total:                       164
+
+
+*******************************************************************************"
+            .as_bytes();
+
+        let summary = parse_module_summaries(input).unwrap();
+        let keys: Vec<String> = summary.objects().iter().map(|(key, _)| key.to_string()).collect();
+        assert_eq!(keys, vec!["crc.o (vendor.a)", "util.o (vendor.a)", "crc.o (vendor.a)", "crc.o (inhouse.a)"]);
+
+        let key = ObjectKey{ archive: "vendor.a".into(), object: "crc.o".into() };
+        let duplicates = summary.duplicates();
+        assert_eq!(duplicates, vec![Duplicate{ key, lines: vec![8, 10] }]);
+        assert_eq!(duplicates[0].to_string(), "crc.o (vendor.a) is listed 2 times, on lines 8, 10");
+    }
+
+    #[test]
+    fn test_parse_module_summaries_wide_columns() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code     ro data  rw data
+    ------                         ----------  -------  -------
+ExtFlash.a: [1]
+    Image.o                        12 345 678       24
+    --------------------------------------------------------------
+    Total:                         12 345 678       24
+
+------------------------------------------------------------------
+    Grand Total:                   12 345 678       24
+
+
+*******************************************************************************"
+            .as_bytes();
+
+        let summary = parse_module_summaries(input).unwrap();
+        let expected = Module{ ro_code: Some(12_345_678), ro_data: Some(24), rw_data: None, ..Default::default() };
+        assert_eq!(summary.tables[0].get("Image.o"), Some(&expected));
+        assert_eq!(summary.grand_total, Some(expected));
+    }
+
+    #[test]
+    fn test_parse_module_summaries_missing() {
+        let input = "*** ENTRY LIST\n***\n".as_bytes();
+        match parse_module_summaries(input) {
+            Err(Error::MissingSection("MODULE SUMMARY")) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_module_summaries_unsupported_layout() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code
+    ------                         -------  -------
+"
+            .as_bytes();
+        match parse_module_summaries(input) {
+            Err(Error::UnsupportedLayout { section: "MODULE SUMMARY", line: 5, ref text }) => {
+                assert_eq!(text, "    Module                         ro code")
+            }
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_module_summaries_unknown_column() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  rw code  rw data
+    ------                         -------  -------  -------
+Startup.a: [1]
+    Startup.o                           40       12        8
+    --------------------------------------------------------
+    Total:                              40       12        8
+
+------------------------------------------------------------
+    Grand Total:                        40       12        8
+
+
+*******************************************************************************"
+            .as_bytes();
+
+        let summary = parse_module_summaries(input).unwrap();
+        let expected = Module{ ro_code: Some(40), rw_data: Some(8), other: vec![("rw code".into(), Some(12))], ..Default::default() };
+        assert_eq!(summary.tables[0].get("Startup.o"), Some(&expected));
+        assert_eq!(summary.grand_total, Some(expected));
+        assert!(summary.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_module_summaries_malformed_header() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Something else entirely
+"
+            .as_bytes();
+        match parse_module_summaries(input) {
+            Err(Error::MalformedColumnHeader { section: "MODULE SUMMARY", line: 5, .. }) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_module_summaries_malformed_row() {
+        let input = "*******************************************************************************
+*** MODULE SUMMARY
+***
+
+    Module                         ro code  ro data  rw data
+    ------                         -------  -------  -------
+FileSys.a: [3]
+    FAT_Dir.o                          536       24
+    FAT_Bad.o                          5x6
+    --------------------------------------------------------
+    Total:                             536       24
+
+
+*******************************************************************************"
+            .as_bytes();
+        match parse_module_summaries(input) {
+            Err(Error::MalformedRow { section: "MODULE SUMMARY", line: 9, ref text }) => {
+                assert_eq!(text, "    FAT_Bad.o                          5x6")
+            }
+            result => panic!("{:?}", result),
+        }
     }
 
     #[test]
@@ -258,7 +659,7 @@ but we just ramble here to simulate that 'other content.'
             .as_bytes();
 
         let result: IResult<_, _> = many_till!(input, anychar, header);
-        assert_eq!(result.is_done(), true);
+        assert!(result.is_done());
         assert_eq!(result.remaining_input().unwrap(), EMPTY);
     }
 
@@ -286,13 +687,13 @@ Entry                      Address    Size  Type      Object
 .iar.dynexit$$Limit     0x200044e0           --   Gb  - Linker created -"
             .as_bytes();
 
-        named!(p< &[u8], ObjModuleTable>,
-            do_parse!(
-                ts: apply!(tablep, 35) >>
+        fn p(input: &[u8]) -> IResult<&[u8], ObjModuleTable> {
+            do_parse!(input,
+                ts: apply!(tablep, &Layout::standard(35), &Lines::new(input), &mut Warnings::strict()) >>
                 take_until!("*") >>
                 (ts)
             )
-        );
+        }
 
         let result = p(input);
         let rest = "*******************************************************************************
@@ -305,30 +706,37 @@ Entry                      Address    Size  Type      Object
 .iar.dynexit$$Limit     0x200044e0           --   Gb  - Linker created -"
             .as_bytes();
 
-        let mut files: HashMap<String, Module> = HashMap::new();
-        files.insert(
-            "FAT_CheckDisk.o".into(),
-            Module {
-                ro_code: Some(2_924),
-                ro_data: Some(48),
-                rw_data: Some(16),
-            },
-        );
-        files.insert(
-            "FAT_Dir.o".into(),
-            Module {
-                ro_code: Some(536),
-                ro_data: Some(24),
-                rw_data: None,
-            },
-        );
-
-        assert_eq!(result.is_done(), true);
+        let files: Vec<(&str, Module)> = vec![
+            (
+                "FAT_CheckDisk.o",
+                Module {
+                    ro_code: Some(2_924),
+                    ro_data: Some(48),
+                    rw_data: Some(16),
+                    ..Default::default()
+                },
+            ),
+            (
+                "FAT_Dir.o",
+                Module {
+                    ro_code: Some(536),
+                    ro_data: Some(24),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        assert!(result.is_done());
         assert_eq!(&result.remaining_input().unwrap(), &rest);
 
-        if let IResult::Done(_, ObjModuleTable { name, table: map }) = result {
+        if let IResult::Done(_, ObjModuleTable { name, path, index, line, table: map, total }) = result {
             assert_eq!(name, String::from("FileSys.a: [3]"));
-            assert_eq!(map, files);
+            assert_eq!(path, "FileSys.a");
+            assert_eq!(index, Some(3));
+            assert_eq!(line, 1);
+            assert_eq!(map, ModuleRow::consecutive(files, 2));
+            assert_eq!(total, Module{ ro_code: Some(68), ro_data: None, rw_data: None, ..Default::default() });
         } else {
             unreachable!();
         }
@@ -2,27 +2,45 @@
 
 use nom::*;
 
+use error::Error;
+use section::{linep, Lines, Warnings};
+
+use std::convert::TryFrom;
 use std::option::Option;
 use std::str;
 use std::string::String;
-use std::collections::HashMap;
-use std::ops::Sub;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub};
 use std::fmt;
+use std::mem;
 
 /// A module description. All fields are optional.
 ///
-/// Two modules may be subtracted. Values subtract if-and-only-if each is valid
-/// across modules. That is, `Some(v) - None == None`, and `None - Some(v) ==
-/// None`.
+/// The `ro_code`, `ro_data`, and `rw_data` sizes are in every module summary.
+/// The `ro_data_abs` and `rw_data_abs` sizes are the `ro data (abs)` and
+/// `rw data (abs)` columns, which only some linkers include. They are `None` if
+/// the module summary does not have the column. `other` has the sizes of any
+/// other columns, in order, with their titles, such as `rw code`. The other
+/// columns are kept as they are, and are not part of the totals or deltas.
+///
+/// Modules may be added and summed. Subtracting two modules, or references to
+/// them, gives the signed `ModuleDelta` between them. A missing size counts as
+/// zero when the other module has that size. That is, `Some(v) - None ==
+/// Some(v)`, and `None - None == None`. The operators panic on overflow;
+/// `checked_add` and `checked_sub` return `None` instead.
 ///
 /// ```
-/// use iarmap::Module;
+/// use iarmap::{Module, ModuleDelta};
+///
+/// let a = Module{ ro_code: Some(10), ro_data: None, rw_data: Some(20), ..Default::default() };
+/// let b = Module{ ro_code: Some(5), ro_data: Some(4), rw_data: Some(11), ..Default::default() };
 ///
-/// let a = Module{ ro_code: Some(10), ro_data: None, rw_data: Some(20) };
-/// let b = Module{ ro_code: Some(5), ro_data: Some(4), rw_data: Some(11) };
+/// let expected = ModuleDelta{ ro_code: Some(5), ro_data: Some(-4), rw_data: Some(9), ..Default::default() };
+/// assert_eq!(&a - &b, expected);
 ///
-/// let expected = Module{ ro_code: Some(5), ro_data: Some(-4), rw_data: Some(9) };
-/// assert_eq!(a - b, expected);
+/// let total: Module = vec![a, b].into_iter().sum();
+/// assert_eq!(total.flash(), 19);
+/// assert_eq!(total.ram(), 31);
 /// ```
 ///
 /// A `Module` will pretty-print with the `{}` formatter as
@@ -30,82 +48,422 @@ use std::fmt;
 /// ```text
 /// ro_code:    526      ro_data:    436         rw_data: ------
 /// ```
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+///
+/// The absolute columns are appended only if either has a size, followed by
+/// the other columns.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct Module {
-    pub ro_code: Option<i32>,
-    pub ro_data: Option<i32>,
-    pub rw_data: Option<i32>,
+    pub ro_code: Option<u64>,
+    pub ro_data: Option<u64>,
+    pub rw_data: Option<u64>,
+    pub ro_data_abs: Option<u64>,
+    pub rw_data_abs: Option<u64>,
+    /// The sizes of the other columns, by title. Subtracting modules ignores
+    /// them, so a `ModuleDelta`, and the diff of two map files, do not show a
+    /// change in another column, and an object that only changed there is
+    /// unchanged.
+    pub other: Vec<(String, Option<u64>)>,
+}
+
+/// The change in size between two modules, as computed by `Module - Module`.
+/// A size is `None` if neither module has that size. The other columns of a
+/// `Module` have no delta.
+///
+/// Deltas may be added, negated, and summed, and pretty-print like a `Module`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct ModuleDelta {
+    pub ro_code: Option<i64>,
+    pub ro_data: Option<i64>,
+    pub rw_data: Option<i64>,
+    pub ro_data_abs: Option<i64>,
+    pub rw_data_abs: Option<i64>,
+}
+
+/// A row of a module summary table: the object, its sizes, and the 1-based
+/// line of the map file that lists it
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ModuleRow {
+    pub name: String,
+    pub module: Module,
+    pub line: usize,
+}
+
+/// Combines two optional values, counting None as "0" if the other value is
+/// Some(v). Returns None if the combination overflows.
+#[inline]
+fn combine<T, R, F>(left: Option<T>, right: Option<T>, f: F) -> Option<Option<R>>
+where
+    T: Default,
+    F: Fn(T, T) -> Option<R>,
+{
+    match (left, right) {
+        (None, None) => Some(None),
+        (l, r) => f(l.unwrap_or_default(), r.unwrap_or_default()).map(Some),
+    }
+}
+
+/// Subtracts two sizes, if the difference fits in a delta
+fn size_diff(left: u64, right: u64) -> Option<i64> {
+    i64::try_from(left).ok()?.checked_sub(i64::try_from(right).ok()?)
+}
+
+/// Writes the five sizes of a module or delta, appending the absolute columns
+/// only if either has a size
+fn write_sizes<T: fmt::Display + Copy>(f: &mut fmt::Formatter, sizes: [Option<T>; 5]) -> fmt::Result {
+    write!(
+        f,
+        "ro_code: {} \t ro_data: {} \t rw_data: {}",
+        Module::size_to_string(sizes[0]),
+        Module::size_to_string(sizes[1]),
+        Module::size_to_string(sizes[2])
+    )?;
+    if sizes[3].is_some() || sizes[4].is_some() {
+        write!(
+            f,
+            " \t ro_data_abs: {} \t rw_data_abs: {}",
+            Module::size_to_string(sizes[3]),
+            Module::size_to_string(sizes[4])
+        )?;
+    }
+    Ok(())
 }
 
 impl Module {
     /// Returns the total size of the Module; concretely, the sum of the three
-    /// fields.
-    pub fn total(&self) -> i32 {
-        self.ro_code.map_or(0, |v| {
-            self.ro_data.map_or(
-                0,
-                |q| self.rw_data.map_or(0, |r| v + q + r),
-            )
-        })
+    /// fields, counting a missing field as zero, and saturating at `u64::MAX`.
+    /// The absolute columns are left out, since data placed at an absolute
+    /// address, such as a peripheral register, takes up no flash or RAM that
+    /// the linker allocates.
+    pub fn total(&self) -> u64 {
+        self.flash().saturating_add(self.ram())
+    }
+
+    /// Returns the size in flash, the `ro code` and `ro data`, saturating at
+    /// `u64::MAX`
+    pub fn flash(&self) -> u64 {
+        self.ro_code.unwrap_or(0).saturating_add(self.ro_data.unwrap_or(0))
+    }
+
+    /// Returns the size in RAM, the `rw data`
+    pub fn ram(&self) -> u64 {
+        self.rw_data.unwrap_or(0)
     }
 
-    /// Converts the module code size to a string
-    pub fn size_to_string(size: Option<i32>) -> String {
-            match size {
-                Some(v) => format!("{num:>width$}", num = v, width = 6),
-                None => "------".into(),
+    /// Converts a module size, or a change in size, to a string
+    pub fn size_to_string<T: fmt::Display>(size: Option<T>) -> String {
+        match size {
+            Some(v) => format!("{num:>width$}", num = v, width = 6),
+            None => "------".into(),
+        }
+    }
+
+    /// Returns true if the module has a size in either absolute column
+    pub fn has_abs(&self) -> bool {
+        self.ro_data_abs.is_some() || self.rw_data_abs.is_some()
+    }
+
+    /// Returns the size in the other column with the title, if the module has
+    /// that column
+    pub fn other(&self, title: &str) -> Option<Option<u64>> {
+        self.other.iter().find(|(t, _)| t == title).map(|&(_, size)| size)
+    }
+
+    /// Adds two modules, returning `None` if a size overflows. The other
+    /// columns are added by title.
+    pub fn checked_add(self, other: Module) -> Option<Module> {
+        let mut others = self.other;
+        for (title, size) in other.other {
+            match others.iter_mut().find(|(t, _)| *t == title) {
+                Some(sum) => sum.1 = combine(sum.1, size, u64::checked_add)?,
+                None => others.push((title, size)),
+            }
         }
-    }   
+        Some(Module {
+            ro_code: combine(self.ro_code, other.ro_code, u64::checked_add)?,
+            ro_data: combine(self.ro_data, other.ro_data, u64::checked_add)?,
+            rw_data: combine(self.rw_data, other.rw_data, u64::checked_add)?,
+            ro_data_abs: combine(self.ro_data_abs, other.ro_data_abs, u64::checked_add)?,
+            rw_data_abs: combine(self.rw_data_abs, other.rw_data_abs, u64::checked_add)?,
+            other: others,
+        })
+    }
+
+    /// Returns the change from `other` to this module, or `None` if a change
+    /// does not fit in an `i64`. The other columns are not compared.
+    pub fn checked_sub(&self, other: &Module) -> Option<ModuleDelta> {
+        Some(ModuleDelta {
+            ro_code: combine(self.ro_code, other.ro_code, size_diff)?,
+            ro_data: combine(self.ro_data, other.ro_data, size_diff)?,
+            rw_data: combine(self.rw_data, other.rw_data, size_diff)?,
+            ro_data_abs: combine(self.ro_data_abs, other.ro_data_abs, size_diff)?,
+            rw_data_abs: combine(self.rw_data_abs, other.rw_data_abs, size_diff)?,
+        })
+    }
 }
 
-/// Subtract two optional values, counting None as "0" if the other value is
-/// Some(v)
-#[inline]
-fn optional_diff(left: Option<i32>, right: Option<i32>) -> Option<i32> {
-    match (left, right) {
-        (Some(l), Some(r)) => Some(l - r),
-        (Some(l), None) => Some(l),
-        (None, Some(r)) => Some(-r),
-        (None, None) => None
+impl Add for Module {
+    type Output = Module;
+    fn add(self, other: Module) -> Module {
+        self.checked_add(other).expect("module size overflow")
+    }
+}
+
+impl AddAssign for Module {
+    fn add_assign(&mut self, other: Module) {
+        *self = mem::take(self) + other;
+    }
+}
+
+impl Sum for Module {
+    fn sum<I: Iterator<Item = Module>>(iter: I) -> Module {
+        iter.fold(Module::default(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Module> for Module {
+    fn sum<I: Iterator<Item = &'a Module>>(iter: I) -> Module {
+        iter.cloned().sum()
     }
 }
 
 impl Sub for Module {
-    type Output = Module;
-    fn sub(self, other: Module) -> Module {
-        let ro_code = optional_diff(self.ro_code, other.ro_code);
-        let ro_data = optional_diff(self.ro_data, other.ro_data);
-        let rw_data = optional_diff(self.rw_data, other.rw_data);
-        Module {
-            ro_code,
-            ro_data,
-            rw_data,
-        }
+    type Output = ModuleDelta;
+    fn sub(self, other: Module) -> ModuleDelta {
+        &self - &other
+    }
+}
+
+impl<'a> Sub for &'a Module {
+    type Output = ModuleDelta;
+    fn sub(self, other: &'a Module) -> ModuleDelta {
+        self.checked_sub(other).expect("module size delta overflow")
     }
 }
 
 impl fmt::Display for Module {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let ro_code: String = Module::size_to_string(self.ro_code);
-        let ro_data: String = Module::size_to_string(self.ro_data);
-        let rw_data: String = Module::size_to_string(self.rw_data);
-        write!(
-            f,
-            "ro_code: {} \t ro_data: {} \t rw_data: {}",
-            ro_code,
-            ro_data,
-            rw_data
-        )
+        write_sizes(f, [self.ro_code, self.ro_data, self.rw_data, self.ro_data_abs, self.rw_data_abs])?;
+        for (title, size) in &self.other {
+            write!(f, " \t {}: {}", title, Module::size_to_string(*size))?;
+        }
+        Ok(())
+    }
+}
+
+impl ModuleDelta {
+    /// Returns the total change of the three fields, counting a missing field
+    /// as zero, and saturating at the bounds of an `i64`. As with
+    /// `Module::total`, the absolute columns are left out.
+    pub fn total(&self) -> i64 {
+        self.flash().saturating_add(self.ram())
+    }
+
+    /// Returns the change in flash, the `ro code` and `ro data`, saturating at
+    /// the bounds of an `i64`
+    pub fn flash(&self) -> i64 {
+        self.ro_code.unwrap_or(0).saturating_add(self.ro_data.unwrap_or(0))
+    }
+
+    /// Returns the change in RAM, the `rw data`
+    pub fn ram(&self) -> i64 {
+        self.rw_data.unwrap_or(0)
+    }
+
+    /// Returns true if no size changed, not counting the other columns
+    pub fn is_zero(&self) -> bool {
+        [self.ro_code, self.ro_data, self.rw_data, self.ro_data_abs, self.rw_data_abs]
+            .iter()
+            .all(|change| change.unwrap_or(0) == 0)
     }
+
+    /// Returns true if the delta has a change in either absolute column
+    pub fn has_abs(&self) -> bool {
+        self.ro_data_abs.is_some() || self.rw_data_abs.is_some()
+    }
+
+    /// Adds two deltas, returning `None` if a change overflows
+    pub fn checked_add(self, other: ModuleDelta) -> Option<ModuleDelta> {
+        Some(ModuleDelta {
+            ro_code: combine(self.ro_code, other.ro_code, i64::checked_add)?,
+            ro_data: combine(self.ro_data, other.ro_data, i64::checked_add)?,
+            rw_data: combine(self.rw_data, other.rw_data, i64::checked_add)?,
+            ro_data_abs: combine(self.ro_data_abs, other.ro_data_abs, i64::checked_add)?,
+            rw_data_abs: combine(self.rw_data_abs, other.rw_data_abs, i64::checked_add)?,
+        })
+    }
+}
+
+impl Add for ModuleDelta {
+    type Output = ModuleDelta;
+    fn add(self, other: ModuleDelta) -> ModuleDelta {
+        self.checked_add(other).expect("module size delta overflow")
+    }
+}
+
+impl AddAssign for ModuleDelta {
+    fn add_assign(&mut self, other: ModuleDelta) {
+        *self = *self + other;
+    }
+}
+
+impl Neg for ModuleDelta {
+    type Output = ModuleDelta;
+    fn neg(self) -> ModuleDelta {
+        let neg = |v: Option<i64>| v.map(|v| v.checked_neg().expect("module size delta overflow"));
+        ModuleDelta {
+            ro_code: neg(self.ro_code),
+            ro_data: neg(self.ro_data),
+            rw_data: neg(self.rw_data),
+            ro_data_abs: neg(self.ro_data_abs),
+            rw_data_abs: neg(self.rw_data_abs),
+        }
+    }
+}
+
+impl Sum for ModuleDelta {
+    fn sum<I: Iterator<Item = ModuleDelta>>(iter: I) -> ModuleDelta {
+        iter.fold(ModuleDelta::default(), Add::add)
+    }
+}
+
+impl fmt::Display for ModuleDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_sizes(f, [self.ro_code, self.ro_data, self.rw_data, self.ro_data_abs, self.rw_data_abs])
+    }
+}
+
+/// A size column of the module summary. `Other` is a column with any other
+/// title, such as `rw code`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Column {
+    RoCode,
+    RoData,
+    RwData,
+    RoDataAbs,
+    RwDataAbs,
+    Other(String),
+}
+
+impl Column {
+    /// Returns the column with the title used in the column header, such as
+    /// `ro code`
+    fn from_title(title: &str) -> Column {
+        match title {
+            "ro code" => Column::RoCode,
+            "ro data" => Column::RoData,
+            "rw data" => Column::RwData,
+            "ro data (abs)" => Column::RoDataAbs,
+            "rw data (abs)" => Column::RwDataAbs,
+            title => Column::Other(title.into()),
+        }
+    }
+}
+
+/// The layout of a module summary table, derived from the column header and
+/// the ------- underline beneath it.
+///
+/// Sizes are right-aligned to the end of their underline. The linker widens an
+/// underline when a size needs more room, so a size column spans from the end
+/// of the previous column to the end of its own underline.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Layout {
+    /// The width of the name column, which is where the first size column
+    /// starts
+    pub name_width: usize,
+    /// The size columns in order, each with the offset just past its end
+    pub columns: Vec<(Column, usize)>,
+}
+
+/// Returns the start and end offsets of each run of '-'
+fn dash_runs(underline: &[u8]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, c) in underline.iter().enumerate() {
+        match (*c == b'-', start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push((s, i));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        runs.push((s, underline.len()));
+    }
+    runs
+}
+
+/// Returns the bytes between `start` and `end`, clamped to the input length
+#[inline]
+fn clamped(bs: &[u8], start: usize, end: usize) -> &[u8] {
+    let len = bs.len();
+    &bs[start.min(len)..end.min(len)]
+}
+
+/// Returns true if the bytes are only whitespace
+#[inline]
+fn is_blank(bs: &[u8]) -> bool {
+    bs.iter().all(|c| c.is_ascii_whitespace())
 }
 
-const MODULE_DATA_SIZE_MAX_BYTES: usize = 7;
-const MODULE_DATA_SIZE_DELIMITING_BYTES: usize = 2;
-const RO_CODE: usize = MODULE_DATA_SIZE_MAX_BYTES;
-const RO_CODE_RO_DATA: usize = 2 * MODULE_DATA_SIZE_MAX_BYTES + MODULE_DATA_SIZE_DELIMITING_BYTES;
-const RO_CODE_RO_DATA_RW_DATA: usize = 3 * MODULE_DATA_SIZE_MAX_BYTES +
-    2 * MODULE_DATA_SIZE_DELIMITING_BYTES;
+impl Layout {
+    /// Derives the layout from the column header and its underline. The first
+    /// underline is beneath `Module`, and each of the following underlines is
+    /// beneath a size column. Returns `None` if the header is not a module
+    /// summary header, or if a column has no title.
+    pub fn from_header(header: &[u8], underline: &[u8]) -> Option<Layout> {
+        let runs = dash_runs(underline);
+        let (module, sizes) = runs.split_first()?;
+        if clamped(header, module.0, module.1) != b"Module" || sizes.is_empty() {
+            return None;
+        }
+
+        let name_width = sizes[0].0;
+        let mut start = name_width;
+        let mut columns = Vec::new();
+        for &(_, end) in sizes {
+            let title = str::from_utf8(clamped(header, start, end)).ok()?.trim();
+            if title.is_empty() {
+                return None;
+            }
+            columns.push((Column::from_title(title), end));
+            start = end;
+        }
+
+        if is_blank(clamped(header, start, header.len())) {
+            Some(Layout { name_width, columns })
+        } else {
+            None
+        }
+    }
+
+    /// Parses the sizes of a table row. Returns `None` if a column has
+    /// something other than a size, or if the row extends past the last
+    /// column.
+    fn sizes(&self, row: &[u8]) -> Option<Module> {
+        let mut module = Module::default();
+        let mut start = self.name_width;
+        for &(ref column, end) in &self.columns {
+            let size = sizep(clamped(row, start, end))?;
+            match *column {
+                Column::RoCode => module.ro_code = size,
+                Column::RoData => module.ro_data = size,
+                Column::RwData => module.rw_data = size,
+                Column::RoDataAbs => module.ro_data_abs = size,
+                Column::RwDataAbs => module.rw_data_abs = size,
+                Column::Other(ref title) => module.other.push((title.clone(), size)),
+            }
+            start = end;
+        }
 
+        if is_blank(clamped(row, start, row.len())) {
+            Some(module)
+        } else {
+            None
+        }
+    }
+}
 
 /// Utility that accepts bytes and possibly returns a string without spaces
 #[inline]
@@ -117,15 +475,22 @@ fn bs_to_spaceless_string(bs: &[u8]) -> Option<String> {
     }
 }
 
+/// Utility that accepts bytes and possibly returns a string without leading
+/// or trailing spaces
+#[inline]
+fn bs_to_trimmed_string(bs: &[u8]) -> Option<String> {
+    str::from_utf8(bs).ok().map(|s| s.trim_matches(' ').into())
+}
+
 /// Parses the obj file name
 fn _namep(input: &[u8], nbytes: usize) -> IResult<&[u8], Option<String>> {
-    map!(input, take!(nbytes), bs_to_spaceless_string)
+    map!(input, take!(nbytes), bs_to_trimmed_string)
 }
 
-/// Parses the obj file name. If a table end is detected,
-/// a parser error is thrown to force a backtrack and end the
+/// Parses the obj file name. If a table end is detected, or if the name is not
+/// valid UTF-8, a parser error is thrown to force a backtrack and end the
 /// parsing.
-fn namep(input: &[u8], nbytes: usize) -> IResult<&[u8], Option<String>> {
+fn namep(input: &[u8], nbytes: usize) -> IResult<&[u8], String> {
     let result = _namep(input, nbytes);
     match result {
         IResult::Done(rest, Some(name)) => {
@@ -135,117 +500,115 @@ fn namep(input: &[u8], nbytes: usize) -> IResult<&[u8], Option<String>> {
                 // This is inefficient, but it gets the job done.
                 IResult::Error(ErrorKind::Custom(99))
             } else {
-                IResult::Done(rest, Some(name))
+                IResult::Done(rest, name)
             }
         }
-        _ => {
-            println!("Warning: name parser failed: {:?}", result);
-            result
-        }
+        IResult::Done(_, None) => IResult::Error(ErrorKind::Custom(98)),
+        IResult::Error(e) => IResult::Error(e),
+        IResult::Incomplete(n) => IResult::Incomplete(n),
     }
 }
 
-/// Parses a section size
-named!(sizep<&[u8], Option<i32> >,
-    map!(
-        map_opt!(
-            take!(MODULE_DATA_SIZE_MAX_BYTES),
-            bs_to_spaceless_string
-        ),
-        |s| s.parse::<i32>().ok()
-    )
-);
-
-/// Parses ro_code only
-named!(size_ro_code<&[u8], Module>,
-    map!(
-        sizep,
-        |s| { Module{ ro_code: s, ro_data: None, rw_data: None}}
-    )
-);
-
-/// Parses ro_code and ro_data
-named!(size_ro_code_ro_data<&[u8], Module>,
-    map!(
-        do_parse!(
-            ro_code: sizep >> take!(MODULE_DATA_SIZE_DELIMITING_BYTES) >>
-            ro_data: sizep >>
-            (ro_code, ro_data)
-        ),
-        |s| { Module{ ro_code: s.0, ro_data: s.1, rw_data: None}}
-    )
-);
-
-/// Parses ro_code, ro_data, and rw_data
-named!(size_ro_code_ro_data_rw_data<&[u8], Module>,
-    map!(
-        do_parse!(
-            ro_code: sizep >> take!(MODULE_DATA_SIZE_DELIMITING_BYTES) >>
-            ro_data: sizep >> take!(MODULE_DATA_SIZE_DELIMITING_BYTES) >>
-            rw_data: sizep >>
-            (ro_code, ro_data, rw_data)
-        ),
-        |s| { Module{ ro_code: s.0, ro_data: s.1, rw_data: s.2}}
-    )
-);
-
-/// Accepts the remaining input, as well as bytes representing the modules
-/// sizes. Chooses an appropriate parses based on the length of the sizes slice,
-/// and parses a Module value. Returns the remaining input and the module.
-fn _size_rowp<'a>(input: &'a [u8], sizes: &[u8]) -> IResult<&'a [u8], Module> {
-    let len = sizes.len();
-
-    let parser: fn(&[u8]) -> IResult<&[u8], Module> = if len == RO_CODE {
-        size_ro_code
-    } else if len == RO_CODE_RO_DATA {
-        size_ro_code_ro_data
-    } else if len == RO_CODE_RO_DATA_RW_DATA {
-        size_ro_code_ro_data_rw_data
+/// Parses a size column. A blank column is a missing size, and anything other
+/// than digits and spaces is a parse failure.
+fn sizep(bs: &[u8]) -> Option<Option<u64>> {
+    let s = bs_to_spaceless_string(bs)?;
+    if s.is_empty() {
+        Some(None)
     } else {
-        return IResult::Error(ErrorKind::Custom(0));
-    };
-
-    match parser(&sizes) {
-        IResult::Done(_, module) => IResult::Done(input, module),
-        _ => IResult::Error(ErrorKind::Custom(0)),
-    }
-}
-
-/// Parses a row of module sizes
-named!(size_rowp<&[u8], Module>,
-    do_parse!(
-        bs: not_line_ending >>
-        m: apply!(_size_rowp, bs) >>
-        (m)
-    )
-);
-
-/// Parses the module name and sizes
-fn modulep(input: &[u8], nbytes: usize) -> IResult<&[u8], (String, Module)> {
-    do_parse!(input,
-        name: apply!(namep, nbytes) >>
-        module: size_rowp >>
-        ((name.expect("No obj file name parsed from modulep"), module))
-    )
-}
-
-/// Parses a complete module summary table row
-fn table_rowp(input: &[u8], nbytes: usize) -> IResult<&[u8], (String, Module)> {
-    do_parse!(input,
-        m: apply!(modulep, nbytes) >>
-        line_ending >>
-        (m)
-    )
-}
-
-/// Parses all module summary table rows, and inserts the results into a HashMap
-pub fn module_table(input: &[u8], nbytes: usize) -> IResult<&[u8], HashMap<String, Module>> {
-    fold_many0!(input, apply!(table_rowp, nbytes), HashMap::new(),
-        |mut hm: HashMap<_,_>, sm: (String, Module)| {
-            hm.insert(sm.0, sm.1);
-            hm
+        s.parse::<u64>().ok().map(Some)
+    }
+}
+
+/// Parses the module name and sizes, consuming the line ending
+fn modulep<'a>(input: &'a [u8], layout: &Layout) -> IResult<&'a [u8], (String, Module)> {
+    let (rest, line) = try_parse!(input, linep);
+    let (_, name) = try_parse!(line, apply!(namep, layout.name_width.min(line.len())));
+    match layout.sizes(line) {
+        Some(module) => IResult::Done(rest, (name, module)),
+        None => IResult::Error(ErrorKind::Custom(0)),
+    }
+}
+
+/// Parses a labelled summary row, such as `Total:` or `Gaps`, that carries
+/// sizes but no object name. The row may have no sizes at all, in which case
+/// every field of the returned `Module` is `None`. Consumes the line ending.
+pub fn labelled_rowp<'a>(input: &'a [u8], label: &str, layout: &Layout) -> IResult<&'a [u8], Module> {
+    let (rest, line) = try_parse!(input, linep);
+    match str::from_utf8(clamped(line, 0, layout.name_width)) {
+        Ok(name) if name.trim() == label => (),
+        _ => return IResult::Error(ErrorKind::Custom(1)),
+    }
+
+    match layout.sizes(line) {
+        Some(module) => IResult::Done(rest, module),
+        None => IResult::Error(ErrorKind::Custom(0)),
+    }
+}
+
+/// Returns true if the line is meant to be a module row: an indented line that
+/// is neither blank nor a table end
+fn is_module_row(line: &[u8]) -> bool {
+    line.first() == Some(&b' ') && line.iter().any(|c| !c.is_ascii_whitespace() && *c != b'-')
+}
+
+/// Parses all module summary table rows, in the order of the map file. `lines`
+/// indexes the map file, and numbers the rows. A malformed row ends the table
+/// of a strict parse, and is skipped by a lenient one.
+pub fn module_table<'a>(
+    mut input: &'a [u8],
+    layout: &Layout,
+    lines: &Lines,
+    warnings: &mut Warnings,
+) -> IResult<&'a [u8], Vec<ModuleRow>> {
+    let mut rows = Vec::new();
+    loop {
+        match modulep(input, layout) {
+            IResult::Done(rest, (name, module)) if rest.len() < input.len() => {
+                rows.push(ModuleRow { name, module, line: lines.line(input) });
+                input = rest;
+            }
+            _ => match linep(input) {
+                IResult::Done(rest, line) if rest.len() < input.len() && is_module_row(line) => {
+                    if warnings.report(Error::malformed_row_at(super::SECTION, lines.line(input), input)).is_err() {
+                        break;
+                    }
+                    input = rest;
+                }
+                _ => break,
+            },
         }
-    )
+    }
+    IResult::Done(input, rows)
+}
+
+#[cfg(test)]
+impl ModuleRow {
+    /// The rows of a table that lists the modules on consecutive lines,
+    /// starting at `line`
+    pub fn consecutive(modules: Vec<(&str, Module)>, line: usize) -> Vec<ModuleRow> {
+        modules
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, module))| ModuleRow { name: name.into(), module, line: line + i })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl Layout {
+    /// The layout of the standard `ro code  ro data  rw data` header with a
+    /// name column of `nbytes`
+    pub fn standard(nbytes: usize) -> Layout {
+        Layout {
+            name_width: nbytes,
+            columns: vec![
+                (Column::RoCode, nbytes + 7),
+                (Column::RoData, nbytes + 16),
+                (Column::RwData, nbytes + 25),
+            ],
+        }
+    }
 }
 
 #[cfg(test)]
@@ -255,7 +618,6 @@ mod tests {
     use nom::IResult;
 
     static EMPTY: &[u8] = b"";
-    static EOL: &[u8] = b"\n";
 
     #[test]
     fn test_bs_to_spaceless_string() {
@@ -263,29 +625,218 @@ mod tests {
         assert_eq!(bs_to_spaceless_string(bs), Some("123456".into()))
     }
 
+    #[test]
+    fn test_bs_to_trimmed_string() {
+        let bs = "    my file.o   ".as_bytes();
+        assert_eq!(bs_to_trimmed_string(bs), Some("my file.o".into()))
+    }
+
+    #[test]
+    fn test_namep_with_spaces() {
+        let bs = "    my vendor file.o               ".as_bytes();
+        let (rest, name) = namep(bs, 35).unwrap();
+        assert_eq!(name, "my vendor file.o");
+        assert_eq!(rest, EMPTY);
+    }
+
     #[test]
     fn test_namep() {
         let bs = "    MVC_State_Observer_Interface.o ".as_bytes();
         let (rest, name) = namep(bs, 35).unwrap();
-        assert_eq!(name, Some("MVC_State_Observer_Interface.o".into()));
+        assert_eq!(name, "MVC_State_Observer_Interface.o");
         assert_eq!(rest, EMPTY);
     }
 
     #[test]
     fn test_sizep() {
         let bs = "  1 360".as_bytes();
-        let (rest, size) = sizep(bs).unwrap();
-        assert_eq!(size, Some(1360));
-        assert_eq!(rest, EMPTY);
+        assert_eq!(sizep(bs), Some(Some(1360)));
+    }
+
+    #[test]
+    fn test_sizep_blank() {
+        let bs = "       ".as_bytes();
+        assert_eq!(sizep(bs), Some(None));
+    }
+
+    #[test]
+    fn test_sizep_not_a_number() {
+        let bs = "    5x6".as_bytes();
+        assert_eq!(sizep(bs), None);
+    }
+
+    #[test]
+    fn test_layout_from_header() {
+        let header = "    Module                         ro code  ro data  rw data".as_bytes();
+        let underline = "    ------                         -------  -------  -------".as_bytes();
+        assert_eq!(Layout::from_header(header, underline), Some(Layout::standard(35)));
+    }
+
+    #[test]
+    fn test_layout_from_widened_header() {
+        let header = "    Module                         ro code     ro data  rw data".as_bytes();
+        let underline = "    ------                         ----------  -------  -------".as_bytes();
+        let expected = Layout {
+            name_width: 35,
+            columns: vec![(Column::RoCode, 45), (Column::RoData, 54), (Column::RwData, 63)],
+        };
+        assert_eq!(Layout::from_header(header, underline), Some(expected));
+    }
+
+    #[test]
+    fn test_layout_from_header_abs_columns() {
+        let header = "    Module                         ro code  ro data  rw data  ro data (abs)  rw data (abs)".as_bytes();
+        let underline = "    ------                         -------  -------  -------  -------------  -------------".as_bytes();
+        let expected = Layout {
+            name_width: 35,
+            columns: vec![
+                (Column::RoCode, 42),
+                (Column::RoData, 51),
+                (Column::RwData, 60),
+                (Column::RoDataAbs, 75),
+                (Column::RwDataAbs, 90),
+            ],
+        };
+        assert_eq!(Layout::from_header(header, underline), Some(expected));
+    }
+
+    #[test]
+    fn test_module_abs_columns() {
+        let header = "    Module                         ro code  rw data  rw data (abs)".as_bytes();
+        let underline = "    ------                         -------  -------  -------------".as_bytes();
+        let layout = Layout::from_header(header, underline).unwrap();
+        let bs = "    Peripherals.o                      120                   1 024\n".as_bytes();
+        let expected: (String, Module) = (
+            "Peripherals.o".into(),
+            Module {
+                ro_code: Some(120),
+                rw_data_abs: Some(1_024),
+                ..Default::default()
+            },
+        );
+        assert_eq!(modulep(bs, &layout), IResult::Done(EMPTY, expected));
+    }
+
+    #[test]
+    fn test_display_abs_columns() {
+        let m = Module{ ro_code: Some(120), rw_data_abs: Some(1_024), ..Default::default() };
+        assert_eq!(
+            format!("{}", m),
+            "ro_code:    120 \t ro_data: ------ \t rw_data: ------ \t ro_data_abs: ------ \t rw_data_abs:   1024"
+        );
+    }
+
+    #[test]
+    fn test_total_missing_columns() {
+        let alarm_log = Module{ ro_code: Some(188), ..Default::default() };
+        assert_eq!(alarm_log.total(), 188);
+        assert_eq!(alarm_log.flash(), 188);
+        assert_eq!(alarm_log.ram(), 0);
+        assert_eq!(Module::default().total(), 0);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Module{ ro_code: Some(10), rw_data: Some(4), ..Default::default() };
+        let b = Module{ ro_code: Some(5), ro_data: Some(2), ..Default::default() };
+
+        let expected = Module{ ro_code: Some(15), ro_data: Some(2), rw_data: Some(4), ..Default::default() };
+        assert_eq!(a.clone() + b.clone(), expected);
+
+        let mut c = a.clone();
+        c += b.clone();
+        assert_eq!(c, expected);
+
+        assert_eq!([a, b].iter().sum::<Module>(), expected);
+        assert_eq!(Vec::<Module>::new().into_iter().sum::<Module>(), Module::default());
+    }
+
+    #[test]
+    fn test_delta() {
+        let a = Module{ ro_code: Some(10), rw_data: Some(4), ..Default::default() };
+        let b = Module{ ro_code: Some(5), ro_data: Some(2), ..Default::default() };
+
+        let delta = &a - &b;
+        assert_eq!(delta, ModuleDelta{ ro_code: Some(5), ro_data: Some(-2), rw_data: Some(4), ..Default::default() });
+        assert_eq!(&b - &a, -delta);
+        assert_eq!(delta + (&b - &a), ModuleDelta{ ro_code: Some(0), ro_data: Some(0), rw_data: Some(0), ..Default::default() });
+        assert_eq!(delta.total(), 7);
+        assert_eq!(delta.flash(), 3);
+        assert!(!delta.is_zero());
+        assert!((&a - &a).is_zero());
+        assert_eq!(a - b, delta);
+    }
+
+    #[test]
+    fn test_wide_sizes() {
+        let large = Module{ ro_code: Some(3 << 30), ..Default::default() };
+        assert_eq!(large.total(), 3 << 30);
+        assert_eq!((&large - &Module::default()).ro_code, Some(3 << 30));
+
+        let huge = Module{ ro_code: Some(u64::MAX), ..Default::default() };
+        assert_eq!(huge.clone().checked_add(large.clone()), None);
+        assert_eq!(huge.checked_sub(&large), None);
+        assert_eq!(large.clone().checked_add(large).unwrap().ro_code, Some(6 << 30));
+
+        let full = Module{ ro_code: Some(u64::MAX), ro_data: Some(1), rw_data: Some(1), ..Default::default() };
+        assert_eq!(full.flash(), u64::MAX);
+        assert_eq!(full.total(), u64::MAX);
+        let delta = ModuleDelta{ ro_code: Some(i64::MAX), rw_data: Some(1), ..Default::default() };
+        assert_eq!(delta.total(), i64::MAX);
+    }
+
+    #[test]
+    fn test_layout_from_header_unknown_column() {
+        let header = "    Module                         ro code  rw code  rw data".as_bytes();
+        let underline = "    ------                         -------  -------  -------".as_bytes();
+        let layout = Layout::from_header(header, underline).unwrap();
+        assert_eq!(layout.columns[1], (Column::Other("rw code".into()), 51));
+
+        let row = "    Startup.o                           40       12        8".as_bytes();
+        let module = layout.sizes(row).unwrap();
+        assert_eq!(module.ro_code, Some(40));
+        assert_eq!(module.rw_data, Some(8));
+        assert_eq!(module.other("rw code"), Some(Some(12)));
+        assert_eq!(module.other("xx data"), None);
+        assert_eq!(module.total(), 48);
+
+        let sum = module.clone() + module;
+        assert_eq!(sum.other, vec![("rw code".into(), Some(24))]);
+        assert!(sum.to_string().ends_with(" \t rw code:     24"));
+    }
+
+    #[test]
+    fn test_module_widened_column() {
+        let layout = Layout {
+            name_width: 35,
+            columns: vec![(Column::RoCode, 45), (Column::RoData, 54), (Column::RwData, 63)],
+        };
+        let bs = "    ExternalFlashImage.o           12 345 678    1 024       16\n".as_bytes();
+        let expected: (String, Module) = (
+            "ExternalFlashImage.o".into(),
+            Module {
+                ro_code: Some(12_345_678),
+                ro_data: Some(1_024),
+                rw_data: Some(16),
+                ..Default::default()
+            },
+        );
+        assert_eq!(modulep(bs, &layout), IResult::Done(EMPTY, expected));
+    }
+
+    #[test]
+    fn test_module_past_last_column() {
+        let bs = "    BigFoosBarsBaz.o                   532      569      103     42\n".as_bytes();
+        assert!(modulep(bs, &Layout::standard(35)).is_err());
     }
 
     #[test]
     fn test_module_all_three_values() {
         let bs = "    BigFoosBarsBaz.o                   532      569      103\n".as_bytes();
-        let (rest, namedmod) = modulep(bs, 35).unwrap();
-        assert_eq!(rest, EOL);
+        let (rest, namedmod) = modulep(bs, &Layout::standard(35)).unwrap();
+        assert_eq!(rest, EMPTY);
         assert_eq!(namedmod.0, "BigFoosBarsBaz.o");
-        assert_eq!(namedmod.1, Module{ ro_code: Some(532), ro_data: Some(569), rw_data: Some(103)});
+        assert_eq!(namedmod.1, Module{ ro_code: Some(532), ro_data: Some(569), rw_data: Some(103), ..Default::default() });
     }
 
     #[test]
@@ -297,10 +848,11 @@ mod tests {
                 ro_code: Some(532),
                 ro_data: None,
                 rw_data: None,
+                ..Default::default()
             },
         );
-        let actual = modulep(bs, 35);
-        assert_eq!(actual, IResult::Done(EOL, expected));
+        let actual = modulep(bs, &Layout::standard(35));
+        assert_eq!(actual, IResult::Done(EMPTY, expected));
     }
 
     #[test]
@@ -312,10 +864,11 @@ mod tests {
                 ro_code: None,
                 ro_data: None,
                 rw_data: Some(103),
+                ..Default::default()
             },
         );
-        let actual = modulep(bs, 35);
-        assert_eq!(actual, IResult::Done(EOL, expected));
+        let actual = modulep(bs, &Layout::standard(35));
+        assert_eq!(actual, IResult::Done(EMPTY, expected));
     }
 
     #[test]
@@ -327,10 +880,11 @@ mod tests {
                 ro_code: Some(532),
                 ro_data: None,
                 rw_data: Some(103),
+                ..Default::default()
             },
         );
-        let actual = modulep(bs, 35);
-        assert_eq!(actual, IResult::Done(EOL, expected));
+        let actual = modulep(bs, &Layout::standard(35));
+        assert_eq!(actual, IResult::Done(EMPTY, expected));
     }
 
     #[test]
@@ -342,10 +896,11 @@ mod tests {
                 ro_code: Some(166),
                 ro_data: Some(32),
                 rw_data: None,
+                ..Default::default()
             },
         );
-        let actual = modulep(bs, 35);
-        assert_eq!(actual, IResult::Done(EOL, expected));
+        let actual = modulep(bs, &Layout::standard(35));
+        assert_eq!(actual, IResult::Done(EMPTY, expected));
     }
 
     #[test]
@@ -357,10 +912,11 @@ mod tests {
                 ro_code: None,
                 ro_data: Some(7348),
                 rw_data: Some(128),
+                ..Default::default()
             },
         );
-        let actual = modulep(bs, 35);
-        assert_eq!(actual, IResult::Done(EOL, expected));
+        let actual = modulep(bs, &Layout::standard(35));
+        assert_eq!(actual, IResult::Done(EMPTY, expected));
     }
 
     #[test]
@@ -372,28 +928,61 @@ mod tests {
                 ro_code: None,
                 ro_data: Some(13172),
                 rw_data: None,
+                ..Default::default()
             },
         );
-        let actual = modulep(bs, 35);
-        assert_eq!(actual, IResult::Done(EOL, expected));
+        let actual = modulep(bs, &Layout::standard(35));
+        assert_eq!(actual, IResult::Done(EMPTY, expected));
+    }
+
+    #[test]
+    fn test_labelled_row() {
+        let bs = "    Total:                           385 176  616 821  210 147\n".as_bytes();
+        let expected = Module {
+            ro_code: Some(385_176),
+            ro_data: Some(616_821),
+            rw_data: Some(210_147),
+            ..Default::default()
+        };
+        assert_eq!(labelled_rowp(bs, "Total:", &Layout::standard(37)), IResult::Done(EMPTY, expected));
+    }
+
+    #[test]
+    fn test_labelled_row_without_sizes() {
+        let bs = "    Total:\n    Gaps".as_bytes();
+        let expected = Module {
+            ro_code: None,
+            ro_data: None,
+            rw_data: None,
+            ..Default::default()
+        };
+        assert_eq!(labelled_rowp(bs, "Total:", &Layout::standard(37)), IResult::Done(&b"    Gaps"[..], expected));
+    }
+
+    #[test]
+    fn test_labelled_row_wrong_label() {
+        let bs = "    Gaps                                  96       90        9\n".as_bytes();
+        assert!(labelled_rowp(bs, "Total:", &Layout::standard(37)).is_err());
     }
 
     #[test]
     fn test_module_table_one_row() {
         let table = b"    UI_AbstractFactoryThing.o          724      544\n";
 
-        let mut expected: HashMap<String, Module> = HashMap::new();
-        expected.insert(
-            "UI_AbstractFactoryThing.o".into(),
-            Module {
-                ro_code: Some(724),
-                ro_data: Some(544),
-                rw_data: None,
-            },
-        );
+        let expected: Vec<(&str, Module)> = vec![
+            (
+                "UI_AbstractFactoryThing.o",
+                Module {
+                    ro_code: Some(724),
+                    ro_data: Some(544),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+        ];
 
-        let result = module_table(table, 35);
-        assert_eq!(result, IResult::Done(EMPTY, expected));
+        let result = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
+        assert_eq!(result, IResult::Done(EMPTY, ModuleRow::consecutive(expected, 1)));
 
     }
 
@@ -401,75 +990,102 @@ mod tests {
     fn test_module_table_two_row() {
         let table = b"    UI_AbstractFactoryThing.o          724      544\n    BigFoosBarsBaz.o                   532      569      103\n";
 
-        let mut expected: HashMap<String, Module> = HashMap::new();
-
-        expected.insert(
-            "UI_AbstractFactoryThing.o".into(),
-            Module {
-                ro_code: Some(724),
-                ro_data: Some(544),
-                rw_data: None,
-            },
-        );
-
-        expected.insert(
-            "BigFoosBarsBaz.o".into(),
-            Module {
-                ro_code: Some(532),
-                ro_data: Some(569),
-                rw_data: Some(103),
-            },
-        );
+        let expected: Vec<(&str, Module)> = vec![
+            (
+                "UI_AbstractFactoryThing.o",
+                Module {
+                    ro_code: Some(724),
+                    ro_data: Some(544),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+            (
+                "BigFoosBarsBaz.o",
+                Module {
+                    ro_code: Some(532),
+                    ro_data: Some(569),
+                    rw_data: Some(103),
+                    ..Default::default()
+                },
+            ),
+        ];
 
-        let result = module_table(table, 35);
-        assert_eq!(result, IResult::Done(EMPTY, expected));
+        let result = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
+        assert_eq!(result, IResult::Done(EMPTY, ModuleRow::consecutive(expected, 1)));
     }
 
     #[test]
     fn test_module_table_three_row() {
         let table = b"    UI_AbstractFactoryThing.o          724      544\n    BigFoosBarsBaz.o                   532      569      103\n    UI_FoosBars.o                                88       16\n";
 
-        let mut expected: HashMap<String, Module> = HashMap::new();
-
-        expected.insert(
-            "UI_AbstractFactoryThing.o".into(),
-            Module {
-                ro_code: Some(724),
-                ro_data: Some(544),
-                rw_data: None,
-            },
-        );
-
-        expected.insert(
-            "BigFoosBarsBaz.o".into(),
-            Module {
-                ro_code: Some(532),
-                ro_data: Some(569),
-                rw_data: Some(103),
-            },
-        );
-
-        expected.insert(
-            "UI_FoosBars.o".into(),
-            Module {
-                ro_code: None,
-                ro_data: Some(88),
-                rw_data: Some(16),
-            },
-        );
+        let expected: Vec<(&str, Module)> = vec![
+            (
+                "UI_AbstractFactoryThing.o",
+                Module {
+                    ro_code: Some(724),
+                    ro_data: Some(544),
+                    rw_data: None,
+                    ..Default::default()
+                },
+            ),
+            (
+                "BigFoosBarsBaz.o",
+                Module {
+                    ro_code: Some(532),
+                    ro_data: Some(569),
+                    rw_data: Some(103),
+                    ..Default::default()
+                },
+            ),
+            (
+                "UI_FoosBars.o",
+                Module {
+                    ro_code: None,
+                    ro_data: Some(88),
+                    rw_data: Some(16),
+                    ..Default::default()
+                },
+            ),
+        ];
 
-        let result = module_table(table, 35);
-        assert_eq!(result, IResult::Done(EMPTY, expected));
+        let result = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
+        assert_eq!(result, IResult::Done(EMPTY, ModuleRow::consecutive(expected, 1)));
     }
 
     #[test]
     fn test_module_table_empty_row() {
         let table = b"";
 
-        let empty: HashMap<String, Module> = HashMap::new();
+        let empty: Vec<ModuleRow> = Vec::new();
 
-        let result = module_table(table, 35);
+        let result = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
         assert_eq!(result, IResult::Done(EMPTY, empty));
     }
 
+    #[test]
+    fn test_module_table_malformed_row() {
+        let table = b"    a.o                                 88
+    b.o                                3?0
+    c.o                                168
+    --------------------------------------------------------
+";
+        let end = &table[table.len() - 61..];
+        let a = ("a.o", Module { ro_code: Some(88), ..Default::default() });
+        let c = ("c.o", Module { ro_code: Some(168), ..Default::default() });
+
+        let strict = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::strict());
+        assert_eq!(strict, IResult::Done(&table[43..], ModuleRow::consecutive(vec![a.clone()], 1)));
+
+        let mut warnings = Vec::new();
+        let lenient = module_table(table, &Layout::standard(35), &Lines::new(table), &mut Warnings::lenient(&mut warnings));
+        let mut rows = ModuleRow::consecutive(vec![a, c], 1);
+        rows[1].line = 3;
+        assert_eq!(lenient, IResult::Done(end, rows));
+        match warnings[..] {
+            [Error::MalformedRow { section: "MODULE SUMMARY", line: 2, ref text }] => assert_eq!(text, "    b.o                                3?0"),
+            _ => panic!("{:?}", warnings),
+        }
+    }
+
 }
@@ -0,0 +1,155 @@
+//! Cross-checks the parsed module summary against the linker's own totals
+
+use super::{Module, ModuleSummary};
+
+use std::fmt;
+
+/// Identifies the linker row that disagrees with the parsed rows
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TotalRow {
+    /// The `Total:` row of the named archive
+    Archive(String),
+    /// The `Grand Total:` row of the module summary
+    GrandTotal,
+}
+
+/// A linker total that does not equal the sum of the rows that were parsed.
+///
+/// A mismatch usually means that a size column was parsed incorrectly. The
+/// `linker` member is the row computed by the linker, and the `parsed` member
+/// is the sum of the parsed rows. Missing sizes count as zero in both.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TotalMismatch {
+    pub row: TotalRow,
+    pub linker: Module,
+    pub parsed: Module,
+}
+
+impl fmt::Display for TotalMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.row {
+            TotalRow::Archive(ref name) => write!(f, "Total of {} does not match its rows", name)?,
+            TotalRow::GrandTotal => write!(f, "Grand Total does not match the archives")?,
+        }
+        write!(f, "\n\tlinker: {}\n\tparsed: {}", self.linker, self.parsed)
+    }
+}
+
+/// Sums the modules, counting missing sizes as zero. An absolute column is
+/// only summed if a module has a size in that column.
+fn sum<'a, I: IntoIterator<Item = &'a Module>>(modules: I) -> Module {
+    let zero = Module{ ro_code: Some(0), ro_data: Some(0), rw_data: Some(0), ..Default::default() };
+    zero + modules.into_iter().sum()
+}
+
+/// Returns true if the modules are equal when missing sizes count as zero
+fn same_sizes(left: &Module, right: &Module) -> bool {
+    let zeroed = |m: &Module| {
+        [m.ro_code, m.ro_data, m.rw_data, m.ro_data_abs, m.rw_data_abs].iter().map(|s| s.unwrap_or(0)).collect::<Vec<_>>()
+    };
+    zeroed(left) == zeroed(right)
+}
+
+impl ModuleSummary {
+    /// Compares each archive's `Total:` row against the sum of its parsed rows,
+    /// and the `Grand Total:` row against the sum of every parsed row, `Gaps`,
+    /// and `Linker created`. Returns a `TotalMismatch` for each disagreement,
+    /// or an empty vector if the summary is consistent.
+    pub fn validate(&self) -> Vec<TotalMismatch> {
+        let mut mismatches = Vec::new();
+
+        for obj in &self.tables {
+            let parsed = sum(obj.table.iter().map(|row| &row.module));
+            if !same_sizes(&obj.total, &parsed) {
+                mismatches.push(TotalMismatch {
+                    row: TotalRow::Archive(obj.name.clone()),
+                    linker: obj.total.clone(),
+                    parsed,
+                });
+            }
+        }
+
+        if let Some(ref grand_total) = self.grand_total {
+            let parsed = sum(
+                self.tables
+                    .iter()
+                    .flat_map(|obj| obj.table.iter().map(|row| &row.module))
+                    .chain(self.gaps.iter())
+                    .chain(self.linker_created.iter()),
+            );
+            if !same_sizes(grand_total, &parsed) {
+                mismatches.push(TotalMismatch {
+                    row: TotalRow::GrandTotal,
+                    linker: grand_total.clone(),
+                    parsed,
+                });
+            }
+        }
+
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use summary::ObjModuleTable;
+    use summary::ModuleRow;
+
+    fn module(ro_code: Option<u64>, ro_data: Option<u64>, rw_data: Option<u64>) -> Module {
+        Module{ ro_code, ro_data, rw_data, ..Default::default() }
+    }
+
+    fn summary(total: Module, grand_total: Option<Module>) -> ModuleSummary {
+        let table = vec![("Foo.o", module(Some(10), None, Some(4))), ("Bar.o", module(Some(20), Some(6), None))];
+        ModuleSummary {
+            tables: vec![ObjModuleTable::new("libfoo.a: [1]".into(), 1, ModuleRow::consecutive(table, 2), total)],
+            gaps: Some(module(Some(2), None, None)),
+            linker_created: Some(module(None, None, Some(8))),
+            grand_total,
+        }
+    }
+
+    #[test]
+    fn test_validate_consistent() {
+        let s = summary(
+            module(Some(30), Some(6), Some(4)),
+            Some(module(Some(32), Some(6), Some(12))),
+        );
+        assert_eq!(s.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_validate_archive_mismatch() {
+        let s = summary(module(Some(31), Some(6), Some(4)), None);
+        assert_eq!(
+            s.validate(),
+            vec![
+                TotalMismatch {
+                    row: TotalRow::Archive("libfoo.a: [1]".into()),
+                    linker: module(Some(31), Some(6), Some(4)),
+                    parsed: module(Some(30), Some(6), Some(4)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_grand_total_mismatch() {
+        let s = summary(
+            module(Some(30), Some(6), Some(4)),
+            Some(module(Some(32), Some(6), Some(4))),
+        );
+        assert_eq!(
+            s.validate(),
+            vec![
+                TotalMismatch {
+                    row: TotalRow::GrandTotal,
+                    linker: module(Some(32), Some(6), Some(4)),
+                    parsed: module(Some(32), Some(6), Some(12)),
+                },
+            ]
+        );
+    }
+}